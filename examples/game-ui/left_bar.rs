@@ -27,7 +27,7 @@ impl ui::Component for LeftBarWidget {
     type ReceivedEvent = predefined::button::ButtonEvent;
 
     fn get_layout(&mut self) -> ui::Layout<predefined::button::ButtonEvent> {
-        ui::Layout::VerticalBox(self.buttons.iter_mut().map(|b| b as &mut RawComponent<_>).collect())
+        ui::Layout::VerticalBox(self.buttons.iter_mut().map(|b| b as &mut RawComponent<_>).collect(), 0.0)
     }
 
     fn handle_child_event(&mut self, child_id: usize, _: predefined::button::ButtonEvent)
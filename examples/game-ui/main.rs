@@ -4,6 +4,7 @@ extern crate glutin;
 extern crate ui;
 
 use std::default::Default;
+use std::time::Instant;
 use glium::Surface;
 
 mod left_bar;
@@ -21,13 +22,36 @@ fn main() {
 
     let mut ui: ui::Ui<_, main_ui::MainUiEvent> = ui::Ui::new(<main_ui::MainUi as Default>::default(), ui::Vec2::new(dimensions.0, dimensions.1));
 
+    if let Some(window) = display.get_window() {
+        ui.set_dpi_factor(window.hidpi_factor());
+    }
+
+    let mut last_frame = Instant::now();
+
     'main: loop {
+        let elapsed = last_frame.elapsed();
+        last_frame = Instant::now();
+        ui.tick(elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9);
+
+        // the events are kept until they are polled, even when nobody reacts to them
+        ui.poll_events();
+
         let mut target = display.draw();
         target.clear_color(0.0, 0.0, 0.0, 0.0);
         system.draw(&mut target, &ui);
         target.finish();
 
-        match display.wait_events().next().unwrap() {
+        // while animating, draw the next frame right away instead of waiting for input
+        let event = if ui.is_animating() {
+            match display.poll_events().next() {
+                Some(event) => event,
+                None => continue,
+            }
+        } else {
+            display.wait_events().next().unwrap()
+        };
+
+        match event {
             glutin::Event::Closed => break 'main,
             glutin::Event::Resized(w, h) => {
                 ui.set_viewport(ui::Vec2::new(w, h));
@@ -35,11 +59,42 @@ fn main() {
             glutin::Event::MouseMoved((x, y)) => {
                 ui.set_mouse_position(Some(ui::Vec2::new(x as u32, y as u32)));
             },
-            glutin::Event::MouseInput(glutin::ElementState::Pressed, glutin::MouseButton::Left) => {
-                ui.set_mouse_pressed(true);
+            glutin::Event::MouseInput(state, button) => {
+                let button = match button {
+                    glutin::MouseButton::Left => ui::component::MouseButton::Left,
+                    glutin::MouseButton::Right => ui::component::MouseButton::Right,
+                    glutin::MouseButton::Middle => ui::component::MouseButton::Middle,
+                    _ => continue,
+                };
+
+                ui.set_mouse_button_pressed(button, state == glutin::ElementState::Pressed);
+            },
+            glutin::Event::MouseWheel(glutin::MouseScrollDelta::LineDelta(x, y)) => {
+                ui.scroll(ui::component::ScrollDelta::Lines(ui::Vec2::new(x, y)));
             },
-            glutin::Event::MouseInput(glutin::ElementState::Released, glutin::MouseButton::Left) => {
-                ui.set_mouse_pressed(false);
+            glutin::Event::MouseWheel(glutin::MouseScrollDelta::PixelDelta(x, y)) => {
+                ui.scroll(ui::component::ScrollDelta::Pixels(ui::Vec2::new(x, y)));
+            },
+            glutin::Event::Touch(touch) => {
+                let pointer = ui::component::PointerId::Touch(touch.id);
+                let (x, y) = touch.location;
+                let button = ui::component::MouseButton::Left;
+
+                match touch.phase {
+                    glutin::TouchPhase::Started => {
+                        ui.set_pointer_position(pointer, Some(ui::Vec2::new(x as u32, y as u32)));
+                        ui.set_pointer_button_pressed(pointer, button, true);
+                    },
+                    glutin::TouchPhase::Moved => {
+                        ui.set_pointer_position(pointer, Some(ui::Vec2::new(x as u32, y as u32)));
+                    },
+                    glutin::TouchPhase::Ended => {
+                        ui.set_pointer_position(pointer, Some(ui::Vec2::new(x as u32, y as u32)));
+                        ui.set_pointer_button_pressed(pointer, button, false);
+                        ui.remove_pointer(pointer);
+                    },
+                    glutin::TouchPhase::Cancelled => ui.remove_pointer(pointer),
+                }
             },
             _ => ()
         }
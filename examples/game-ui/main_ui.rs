@@ -30,12 +30,9 @@ impl ui::Component for MainUi {
 
     fn get_layout(&mut self) -> ui::Layout<left_bar::LeftBarWidgetEvent> {
         ui::Layout::PositionnedChildren(vec![
-            ui::PositionnedChild {
-                child: &mut self.left_bar as &mut RawComponent<_>,
-                x: -0.5,
-                y: 0.0,
-            }
-        ])
+            ui::PositionnedChild::anchored(&mut self.left_bar as &mut RawComponent<_>,
+                                           ui::Anchor::Left, 0.0, 0.0)
+        ], None)
     }
 
     fn handle_child_event(&mut self, child_id: usize, _: left_bar::LeftBarWidgetEvent)
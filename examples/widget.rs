@@ -4,6 +4,7 @@ extern crate glutin;
 extern crate ui;
 
 use std::default::Default;
+use std::time::Instant;
 use glium::Surface;
 
 struct MyWidget {
@@ -19,7 +20,7 @@ impl Default for MyWidget {
             value: 0,
             left_button: Default::default(),
             right_button: Default::default(),
-            text: ui::predefined::TextComponent::new("0".to_string(), Default::default(), 0.1),
+            text: ui::predefined::TextComponent::new("0".to_string(), Default::default(), 16.0),
         }
     }
 }
@@ -42,7 +43,7 @@ impl ui::Component for MyWidget {
     type ReceivedEvent = ui::predefined::button::ButtonEvent;
 
     fn get_layout(&mut self) -> ui::Layout<ui::predefined::button::ButtonEvent> {
-        ui::Layout::HorizontalBox(vec![&mut self.left_button, &mut self.text, &mut self.right_button])
+        ui::Layout::HorizontalBox(vec![&mut self.left_button, &mut self.text, &mut self.right_button], 0.0)
     }
 
     fn handle_child_event(&mut self, child_id: usize, _: ui::predefined::button::ButtonEvent)
@@ -70,7 +71,7 @@ impl Default for MyWidgetWithWidgets {
     fn default() -> MyWidgetWithWidgets {
         MyWidgetWithWidgets {
             widgets: vec![Default::default(), Default::default(), Default::default()],
-            text: ui::predefined::TextComponent::new("0".to_string(), Default::default(), 0.1),
+            text: ui::predefined::TextComponent::new("0".to_string(), Default::default(), 16.0),
         }
     }
 }
@@ -82,7 +83,7 @@ impl ui::Component for MyWidgetWithWidgets {
     fn get_layout(&mut self) -> ui::Layout<MyWidgetEvent> {
         let mut b: Vec<_> = self.widgets.iter_mut().map(|w| w as &mut ui::component::RawComponent<_>).collect();
         b.push(&mut self.text);
-        ui::Layout::VerticalBox(b)
+        ui::Layout::VerticalBox(b, 0.0)
     }
 
     fn handle_child_event(&mut self, _: usize, _: MyWidgetEvent) -> Option<()> {
@@ -104,13 +105,36 @@ fn main() {
 
     let mut ui: ui::Ui<_, ()> = ui::Ui::new(<MyWidgetWithWidgets as Default>::default(), ui::Vec2::new(dimensions.0, dimensions.1));
 
+    if let Some(window) = display.get_window() {
+        ui.set_dpi_factor(window.hidpi_factor());
+    }
+
+    let mut last_frame = Instant::now();
+
     'main: loop {
+        let elapsed = last_frame.elapsed();
+        last_frame = Instant::now();
+        ui.tick(elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9);
+
+        // the events are kept until they are polled, even when nobody reacts to them
+        ui.poll_events();
+
         let mut target = display.draw();
         target.clear_color(0.0, 0.0, 0.0, 0.0);
         system.draw(&mut target, &ui);
         target.finish();
 
-        match display.wait_events().next().unwrap() {
+        // while animating, draw the next frame right away instead of waiting for input
+        let event = if ui.is_animating() {
+            match display.poll_events().next() {
+                Some(event) => event,
+                None => continue,
+            }
+        } else {
+            display.wait_events().next().unwrap()
+        };
+
+        match event {
             glutin::Event::Closed => break 'main,
             glutin::Event::Resized(w, h) => {
                 ui.set_viewport(ui::Vec2::new(w, h));
@@ -118,11 +142,42 @@ fn main() {
             glutin::Event::MouseMoved((x, y)) => {
                 ui.set_mouse_position(Some(ui::Vec2::new(x as u32, y as u32)));
             },
-            glutin::Event::MouseInput(glutin::ElementState::Pressed, glutin::MouseButton::Left) => {
-                ui.set_mouse_pressed(true);
+            glutin::Event::MouseInput(state, button) => {
+                let button = match button {
+                    glutin::MouseButton::Left => ui::component::MouseButton::Left,
+                    glutin::MouseButton::Right => ui::component::MouseButton::Right,
+                    glutin::MouseButton::Middle => ui::component::MouseButton::Middle,
+                    _ => continue,
+                };
+
+                ui.set_mouse_button_pressed(button, state == glutin::ElementState::Pressed);
+            },
+            glutin::Event::MouseWheel(glutin::MouseScrollDelta::LineDelta(x, y)) => {
+                ui.scroll(ui::component::ScrollDelta::Lines(ui::Vec2::new(x, y)));
+            },
+            glutin::Event::MouseWheel(glutin::MouseScrollDelta::PixelDelta(x, y)) => {
+                ui.scroll(ui::component::ScrollDelta::Pixels(ui::Vec2::new(x, y)));
             },
-            glutin::Event::MouseInput(glutin::ElementState::Released, glutin::MouseButton::Left) => {
-                ui.set_mouse_pressed(false);
+            glutin::Event::Touch(touch) => {
+                let pointer = ui::component::PointerId::Touch(touch.id);
+                let (x, y) = touch.location;
+                let button = ui::component::MouseButton::Left;
+
+                match touch.phase {
+                    glutin::TouchPhase::Started => {
+                        ui.set_pointer_position(pointer, Some(ui::Vec2::new(x as u32, y as u32)));
+                        ui.set_pointer_button_pressed(pointer, button, true);
+                    },
+                    glutin::TouchPhase::Moved => {
+                        ui.set_pointer_position(pointer, Some(ui::Vec2::new(x as u32, y as u32)));
+                    },
+                    glutin::TouchPhase::Ended => {
+                        ui.set_pointer_position(pointer, Some(ui::Vec2::new(x as u32, y as u32)));
+                        ui.set_pointer_button_pressed(pointer, button, false);
+                        ui.remove_pointer(pointer);
+                    },
+                    glutin::TouchPhase::Cancelled => ui.remove_pointer(pointer),
+                }
             },
             _ => ()
         }
@@ -10,10 +10,11 @@ extern crate ui;
 use std::default::Default;
 use std::path::Path;
 use std::sync::Arc;
-use nalgebra::Mat4;
+use nalgebra::{Mat4, Vec2};
 use glium::Surface;
 
 pub struct UiSystem {
+    display: glium::Display,
     text: glium_text::TextSystem,
     default_font: Arc<glium_text::FontTexture>,
     rectangle: (glium::vertex::VertexBufferAny, glium::IndexBuffer),
@@ -24,6 +25,7 @@ pub struct UiSystem {
 impl UiSystem {
     pub fn new(display: &glium::Display) -> UiSystem {
         UiSystem {
+            display: display.clone(),
             text: glium_text::TextSystem::new(display),
 
             default_font: Arc::new({
@@ -100,41 +102,270 @@ impl UiSystem {
         }
     }
 
+    /// Draws the shapes of a user interface on a surface.
+    ///
+    /// Clipped shapes are drawn with a scissor, which is always perpendicular to the screen. A
+    /// clipped shape inside of a rotation or a skew is therefore drawn inside of the bounding box
+    /// of its rotated clip rectangle, and can overflow the clip rectangle itself.
     pub fn draw<T, U, E>(&self, target: &mut T, ui: &ui::Ui<U, E>) where T: Surface, U: ui::component::RawComponent<E> {
+        // the `em` of the texts is relative to the height of the viewport
+        let (width, height) = target.get_dimensions();
+        let aspect = height as f32 / width as f32;
+
         for shape in ui.draw().iter() {
-            match shape {
-                &ui::Shape::Point { .. } => unimplemented!(),
-                &ui::Shape::Line { .. } => unimplemented!(),
-
-                &ui::Shape::Rectangle { ref from, ref to, ref color } => {
-                    let uniforms = uniform! {
-                        matrix: [
-                            [(to.x - from.x) / 2.0, 0.0, 0.0, 0.0],
-                            [0.0, (to.y - from.y) / 2.0, 0.0, 0.0],
-                            [0.0, 0.0, 1.0, 0.0],
-                            [from.x, from.y, 0.0, 1.0f32]
-                        ],
-                        color: color.clone()
-                    };
-
-                    target.draw(&self.rectangle.0, &self.rectangle.1, &self.rectangles_program,
-                                &uniforms, &Default::default());
-                },
+            self.draw_shape(target, shape, &IDENTITY, aspect, None);
+        }
+    }
 
-                &ui::Shape::Text { ref text, ref font, ref bottom_left, ref em } => {
-                    let text = glium_text::TextDisplay::new(&self.text, self.default_font.clone(),
-                                                            &text[..]);
+    /// Draws a shape whose coordinates are turned into normalized coordinates by `matrix`. If
+    /// `scissor` is `Some`, only the pixels inside of it are modified.
+    ///
+    /// `aspect` is the factor by which the width of the letters must be multiplied, which is
+    /// only different from `1.0` for the texts that `matrix` doesn't scale.
+    fn draw_shape<T>(&self, target: &mut T, shape: &ui::Shape, matrix: &[[f32; 4]; 4],
+                     aspect: f32, scissor: Option<glium::Rect>) where T: Surface
+    {
+        match shape {
+            &ui::Shape::Point { .. } => unimplemented!(),
+            &ui::Shape::Line { .. } => unimplemented!(),
 
-                    let mat = Mat4::new(*em, 0.0, 0.0, bottom_left.x,       // TODO: perspective
-                                           0.0, *em, 0.0, bottom_left.y,
-                                           0.0,   0.0, 1.0, 0.0,
-                                           0.0,   0.0, 0.0, 1.0);
+            &ui::Shape::Rectangle { ref from, ref to, ref color } => {
+                let uniforms = uniform! {
+                    matrix: multiply(matrix, &[
+                        [(to.x - from.x) / 2.0, 0.0, 0.0, 0.0],
+                        [0.0, (to.y - from.y) / 2.0, 0.0, 0.0],
+                        [0.0, 0.0, 1.0, 0.0],
+                        [from.x, from.y, 0.0, 1.0f32]
+                    ]),
+                    color: color.clone()
+                };
 
-                    glium_text::draw(&text, &self.text, target, mat, (1.0, 1.0, 1.0, 1.0));
-                },
+                let parameters = glium::DrawParameters {
+                    scissor: scissor,
+                    .. Default::default()
+                };
+
+                target.draw(&self.rectangle.0, &self.rectangle.1, &self.rectangles_program,
+                            &uniforms, &parameters);
+            },
+
+            &ui::Shape::Text { ref text, ref font, ref bottom_left, ref em } => {
+                let text = glium_text::TextDisplay::new(&self.text, self.default_font.clone(),
+                                                        &text[..]);
+
+                let m = multiply(matrix, &[     // TODO: perspective
+                    [*em * aspect, 0.0, 0.0, 0.0],
+                    [0.0, *em, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [bottom_left.x, bottom_left.y, 0.0, 1.0]
+                ]);
+
+                let mat = Mat4::new(m[0][0], m[1][0], m[2][0], m[3][0],
+                                    m[0][1], m[1][1], m[2][1], m[3][1],
+                                    m[0][2], m[1][2], m[2][2], m[3][2],
+                                    m[0][3], m[1][3], m[2][3], m[3][3]);
+
+                let scissor = match scissor {
+                    Some(scissor) => scissor,
+                    None => {
+                        glium_text::draw(&text, &self.text, target, mat, (1.0, 1.0, 1.0, 1.0));
+                        return;
+                    },
+                };
+
+                // glium_text doesn't take draw parameters, so the text is drawn on a texture
+                // of the size of the target which is then copied with the scissor
+                let (width, height) = target.get_dimensions();
+                let texture = glium::texture::Texture2d::empty(&self.display, width, height);
+                {
+                    let mut surface = texture.as_surface();
+                    surface.clear_color(0.0, 0.0, 0.0, 0.0);
+                    glium_text::draw(&text, &self.text, &mut surface, mat, (1.0, 1.0, 1.0, 1.0));
+                }
+
+                let uniforms = uniform! {
+                    matrix: [
+                        [1.0, 0.0, 0.0, 0.0],
+                        [0.0, 1.0, 0.0, 0.0],
+                        [0.0, 0.0, 1.0, 0.0],
+                        [-1.0, -1.0, 0.0, 1.0f32]
+                    ],
+                    texture: &texture
+                };
+
+                let parameters = glium::DrawParameters {
+                    scissor: Some(scissor),
+                    blending_function: Some(glium::BlendingFunction::Addition {
+                        source: glium::LinearBlendingFactor::SourceAlpha,
+                        destination: glium::LinearBlendingFactor::OneMinusSourceAlpha,
+                    }),
+                    .. Default::default()
+                };
 
-                _ => {}
-            }
+                target.draw(&self.rectangle.0, &self.rectangle.1, &self.images_program,
+                            &uniforms, &parameters);
+            },
+
+            &ui::Shape::Transformed { ref transform, ref shape } => {
+                let t = &transform.matrix;
+                let matrix = multiply(matrix, &[
+                    [t[0][0], t[1][0], 0.0, 0.0],
+                    [t[0][1], t[1][1], 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [t[0][2], t[1][2], 0.0, 1.0]
+                ]);
+
+                // the transformation includes the normalization, which also scales the texts
+                self.draw_shape(target, shape, &matrix, 1.0, scissor);
+            },
+
+            &ui::Shape::Clipped { ref from, ref to, ref shape } => {
+                // a rectangle that is rotated by `matrix` is approximated by its bounding box,
+                // since scissors are always perpendicular to the screen
+                let corners = [apply(matrix, from), apply(matrix, to),
+                               apply(matrix, &Vec2::new(from.x, to.y)),
+                               apply(matrix, &Vec2::new(to.x, from.y))];
+                let min = corners.iter().fold(corners[0], |a, c| {
+                    Vec2::new(a.x.min(c.x), a.y.min(c.y))
+                });
+                let max = corners.iter().fold(corners[0], |a, c| {
+                    Vec2::new(a.x.max(c.x), a.y.max(c.y))
+                });
+
+                // nested clipped shapes are only drawn inside all of their rectangles
+                let rect = to_pixels(target.get_dimensions(), &min, &max);
+                let rect = match scissor {
+                    Some(ref scissor) => intersection(scissor, &rect),
+                    None => rect,
+                };
+
+                self.draw_shape(target, shape, matrix, aspect, Some(rect));
+            },
+
+            _ => {}
+        }
+    }
+}
+
+/// Matrix that doesn't change anything, with the layout of the uniforms.
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Multiplies two matrices whose inner arrays are columns, like the ones of the uniforms. The
+/// result applies `b`, then `a`.
+fn multiply(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut result = [[0.0; 4]; 4];
+
+    for column in 0 .. 4 {
+        for row in 0 .. 4 {
+            result[column][row] = (0 .. 4).map(|k| a[k][row] * b[column][k])
+                                          .fold(0.0, |x, y| x + y);
         }
     }
+
+    result
+}
+
+/// Applies a matrix whose inner arrays are columns to a position.
+fn apply(matrix: &[[f32; 4]; 4], position: &Vec2<f32>) -> Vec2<f32> {
+    Vec2::new(matrix[0][0] * position.x + matrix[1][0] * position.y + matrix[3][0],
+              matrix[0][1] * position.x + matrix[1][1] * position.y + matrix[3][1])
+}
+
+/// Converts a rectangle in normalized coordinates into the pixels of a surface of the given
+/// dimensions.
+fn to_pixels(dimensions: (u32, u32), from: &Vec2<f32>, to: &Vec2<f32>) -> glium::Rect {
+    let pixel = |v: f32, size: u32| {
+        let v = ((v + 1.0) / 2.0 * size as f32).round().max(0.0) as u32;
+        std::cmp::min(v, size)
+    };
+
+    let left = pixel(from.x.min(to.x), dimensions.0);
+    let right = pixel(from.x.max(to.x), dimensions.0);
+    let bottom = pixel(from.y.min(to.y), dimensions.1);
+    let top = pixel(from.y.max(to.y), dimensions.1);
+
+    glium::Rect { left: left, bottom: bottom, width: right - left, height: top - bottom }
+}
+
+/// Returns the pixels that are inside both rectangles. The result is empty if they don't
+/// overlap.
+fn intersection(a: &glium::Rect, b: &glium::Rect) -> glium::Rect {
+    let left = std::cmp::max(a.left, b.left);
+    let bottom = std::cmp::max(a.bottom, b.bottom);
+    let right = std::cmp::max(left, std::cmp::min(a.left + a.width, b.left + b.width));
+    let top = std::cmp::max(bottom, std::cmp::min(a.bottom + a.height, b.bottom + b.height));
+
+    glium::Rect { left: left, bottom: bottom, width: right - left, height: top - bottom }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vec2;
+    use super::{apply, intersection, multiply, to_pixels, IDENTITY};
+
+    fn translation(x: f32, y: f32) -> [[f32; 4]; 4] {
+        [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [x, y, 0.0, 1.0]]
+    }
+
+    fn scale(x: f32, y: f32) -> [[f32; 4]; 4] {
+        [[x, 0.0, 0.0, 0.0], [0.0, y, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]
+    }
+
+    #[test]
+    fn multiply_applies_the_second_matrix_first() {
+        let position = Vec2::new(1.0, 2.0);
+
+        let matrix = multiply(&scale(2.0, 3.0), &translation(1.0, 1.0));
+        assert_eq!(apply(&matrix, &position), Vec2::new(4.0, 9.0));
+
+        let matrix = multiply(&translation(1.0, 1.0), &scale(2.0, 3.0));
+        assert_eq!(apply(&matrix, &position), Vec2::new(3.0, 7.0));
+
+        assert_eq!(multiply(&IDENTITY, &matrix), matrix);
+        assert_eq!(multiply(&matrix, &IDENTITY), matrix);
+    }
+
+    #[test]
+    fn multiply_rotations() {
+        // a quarter turn counter-clockwise
+        let rotation = [[0.0, 1.0, 0.0, 0.0], [-1.0, 0.0, 0.0, 0.0],
+                        [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        assert_eq!(apply(&rotation, &Vec2::new(1.0, 0.0)), Vec2::new(0.0, 1.0));
+
+        let half_turn = multiply(&rotation, &rotation);
+        assert_eq!(apply(&half_turn, &Vec2::new(1.0, 2.0)), Vec2::new(-1.0, -2.0));
+
+        // rotating around `(1.0, 1.0)`
+        let around = multiply(&translation(1.0, 1.0),
+                              &multiply(&rotation, &translation(-1.0, -1.0)));
+        assert_eq!(apply(&around, &Vec2::new(2.0, 1.0)), Vec2::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn pixels() {
+        let left_half = to_pixels((800, 600), &Vec2::new(-1.0, -1.0), &Vec2::new(0.0, 1.0));
+        assert_eq!((left_half.left, left_half.bottom, left_half.width, left_half.height),
+                   (0, 0, 400, 600));
+
+        // the rectangles are clamped to the surface, whatever the order of their corners
+        let corner = to_pixels((800, 600), &Vec2::new(0.5, 2.0), &Vec2::new(-2.0, 0.5));
+        assert_eq!((corner.left, corner.bottom, corner.width, corner.height), (0, 450, 600, 150));
+    }
+
+    #[test]
+    fn scissor_intersection() {
+        let a = to_pixels((100, 100), &Vec2::new(-1.0, -1.0), &Vec2::new(0.0, 0.0));
+        let b = to_pixels((100, 100), &Vec2::new(-0.5, -0.5), &Vec2::new(0.5, 0.5));
+        let both = intersection(&a, &b);
+        assert_eq!((both.left, both.bottom, both.width, both.height), (25, 25, 25, 25));
+
+        let c = to_pixels((100, 100), &Vec2::new(0.5, 0.5), &Vec2::new(1.0, 1.0));
+        let none = intersection(&a, &c);
+        assert_eq!((none.width, none.height), (0, 0));
+    }
 }
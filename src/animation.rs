@@ -0,0 +1,466 @@
+//! Values that change over time.
+//!
+//! Components store tweens and sequences, advance them in `tick`, and read their current value
+//! when rendering or computing their layout. As long as one of them is running, the component
+//! should return `true` from `is_animating` so that the host keeps redrawing.
+//!
+//! Tweens work with anything that implements `Interpolate`: positions and sizes (`Vec2`), colors
+//! (`[f32; 3]` and `[f32; 4]`) and scalars such as an angle or a scale (`f32`).
+
+use nalgebra::Vec2;
+
+/// Values that can be blended between two extremes.
+pub trait Interpolate: Clone {
+    /// Returns the value at `t` between `self` (`t = 0.0`) and `other` (`t = 1.0`).
+    ///
+    /// Some easing curves overshoot, so `t` can be slightly outside of `[0.0, 1.0]`.
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &f32, t: f32) -> f32 {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Vec2<f32> {
+    fn interpolate(&self, other: &Vec2<f32>, t: f32) -> Vec2<f32> {
+        Vec2::new(self.x.interpolate(&other.x, t), self.y.interpolate(&other.y, t))
+    }
+}
+
+impl Interpolate for [f32; 3] {
+    fn interpolate(&self, other: &[f32; 3], t: f32) -> [f32; 3] {
+        [
+            self[0].interpolate(&other[0], t),
+            self[1].interpolate(&other[1], t),
+            self[2].interpolate(&other[2], t),
+        ]
+    }
+}
+
+impl Interpolate for [f32; 4] {
+    fn interpolate(&self, other: &[f32; 4], t: f32) -> [f32; 4] {
+        [
+            self[0].interpolate(&other[0], t),
+            self[1].interpolate(&other[1], t),
+            self[2].interpolate(&other[2], t),
+            self[3].interpolate(&other[3], t),
+        ]
+    }
+}
+
+/// How the progress of a tween is distributed over its duration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+
+    /// Starts slowly and accelerates.
+    EaseIn,
+
+    /// Starts quickly and decelerates.
+    EaseOut,
+
+    /// Starts slowly, accelerates, then decelerates.
+    EaseInOut,
+
+    /// Goes slightly past the end before settling on it.
+    Overshoot,
+}
+
+impl Default for Easing {
+    fn default() -> Easing {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    /// Turns a linear progress between `0.0` and `1.0` into the progress of the curve.
+    pub fn apply(&self, t: f32) -> f32 {
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            },
+            Easing::Overshoot => {
+                let t = t - 1.0;
+                1.0 + t * t * (2.70158 * t + 1.70158)
+            },
+        }
+    }
+}
+
+/// What a tween does once it reaches its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Stops at the end value.
+    Once,
+
+    /// Restarts from the start value, forever.
+    Loop,
+
+    /// Goes back to the start value, then forwards again, forever.
+    PingPong,
+}
+
+impl Default for Repeat {
+    fn default() -> Repeat {
+        Repeat::Once
+    }
+}
+
+/// A value that goes from `from` to `to` over `duration` seconds.
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    /// Value before and at the start of the tween.
+    pub from: T,
+    /// Value at the end of the tween.
+    pub to: T,
+    /// Number of seconds between the start and the end.
+    pub duration: f32,
+    /// Number of seconds to wait before starting. Only applies once, not to each repetition.
+    pub delay: f32,
+    /// How the value progresses between the start and the end.
+    pub easing: Easing,
+    /// What happens at the end.
+    pub repeat: Repeat,
+    /// Number of seconds since the tween was created or restarted.
+    elapsed: f32,
+}
+
+impl<T> Tween<T> where T: Interpolate {
+    /// Builds a linear tween that runs once, without delay.
+    pub fn new(from: T, to: T, duration: f32) -> Tween<T> {
+        Tween {
+            from: from,
+            to: to,
+            duration: duration,
+            delay: 0.0,
+            easing: Default::default(),
+            repeat: Default::default(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Builds a tween that stays on a value for the given number of seconds. Useful as a pause
+    /// in a `Sequence`.
+    pub fn hold(value: T, duration: f32) -> Tween<T> {
+        Tween::new(value.clone(), value, duration)
+    }
+
+    /// Returns the current value.
+    pub fn get(&self) -> T {
+        let t = self.get_progress();
+        self.from.interpolate(&self.to, self.easing.apply(t))
+    }
+
+    /// Returns the progress of the current repetition between `0.0` and `1.0`, before easing.
+    pub fn get_progress(&self) -> f32 {
+        let time = self.elapsed - self.delay;
+
+        if time <= 0.0 {
+            return 0.0;
+        }
+
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        match self.repeat {
+            Repeat::Once => (time / self.duration).min(1.0),
+            Repeat::Loop => (time % self.duration) / self.duration,
+            Repeat::PingPong => {
+                let time = time % (2.0 * self.duration);
+                if time < self.duration {
+                    time / self.duration
+                } else {
+                    2.0 - time / self.duration
+                }
+            },
+        }
+    }
+
+    /// Advances the tween by the given number of seconds.
+    ///
+    /// Returns the part of these seconds that happened after the end of the tween, which is
+    /// always `0.0` for tweens that repeat forever.
+    pub fn advance(&mut self, elapsed: f32) -> f32 {
+        self.elapsed += elapsed;
+
+        match self.repeat {
+            Repeat::Once => {
+                let end = self.delay + self.duration.max(0.0);
+                if self.elapsed > end {
+                    let remaining = (self.elapsed - end).min(elapsed);
+                    self.elapsed = end;
+                    remaining
+                } else {
+                    0.0
+                }
+            },
+
+            // keeping the time within a repetition avoids losing precision
+            Repeat::Loop | Repeat::PingPong if self.duration > 0.0 => {
+                let period = match self.repeat {
+                    Repeat::PingPong => 2.0 * self.duration,
+                    _ => self.duration,
+                };
+
+                if self.elapsed > self.delay + period {
+                    self.elapsed = self.delay + (self.elapsed - self.delay) % period;
+                }
+
+                0.0
+            },
+
+            _ => 0.0,
+        }
+    }
+
+    /// Returns true if the value is still changing or waiting for its delay.
+    pub fn is_running(&self) -> bool {
+        match self.repeat {
+            Repeat::Once => self.elapsed < self.delay + self.duration,
+            Repeat::Loop | Repeat::PingPong => true,
+        }
+    }
+
+    /// Goes back to the start of the tween, including its delay.
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Starts a new tween from the current value to another one, keeping the duration, the
+    /// easing and the repetition. Useful to change the target of a running animation without a
+    /// jump.
+    pub fn retarget(&mut self, to: T) {
+        self.from = self.get();
+        self.to = to;
+        self.delay = 0.0;
+        self.elapsed = 0.0;
+    }
+}
+
+/// Tweens that run one after the other.
+///
+/// The value of the sequence is the one of its current tween. Tweens that repeat forever never
+/// give their place to the next one.
+#[derive(Debug, Clone)]
+pub struct Sequence<T> {
+    steps: Vec<Tween<T>>,
+    /// Whether to restart from the first tween after the last one.
+    looping: bool,
+    current: usize,
+}
+
+impl<T> Sequence<T> where T: Interpolate {
+    /// Builds a sequence that plays the tweens in order, once.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `steps` is empty.
+    pub fn new(steps: Vec<Tween<T>>) -> Sequence<T> {
+        assert!(!steps.is_empty());
+
+        Sequence {
+            steps: steps,
+            looping: false,
+            current: 0,
+        }
+    }
+
+    /// Builds a sequence that plays the tweens in order, and restarts from the first one
+    /// forever.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `steps` is empty.
+    pub fn looping(steps: Vec<Tween<T>>) -> Sequence<T> {
+        let mut sequence = Sequence::new(steps);
+        sequence.looping = true;
+        sequence
+    }
+
+    /// Returns the current value.
+    pub fn get(&self) -> T {
+        self.steps[self.current].get()
+    }
+
+    /// Returns the index of the tween being played.
+    pub fn get_current_step(&self) -> usize {
+        self.current
+    }
+
+    /// Advances the sequence by the given number of seconds.
+    ///
+    /// Returns the part of these seconds that happened after the end of the sequence, which is
+    /// always `0.0` for looping sequences.
+    pub fn advance(&mut self, mut elapsed: f32) -> f32 {
+        loop {
+            let remaining = self.steps[self.current].advance(elapsed);
+
+            if self.steps[self.current].is_running() {
+                return 0.0;
+            }
+
+            if self.current + 1 < self.steps.len() {
+                self.current += 1;
+                elapsed = remaining;
+                continue;
+            }
+
+            if !self.looping {
+                return remaining;
+            }
+
+            self.restart();
+
+            // a sequence without duration would loop forever
+            let total = self.steps.iter().fold(0.0, |t, s| t + s.delay + s.duration.max(0.0));
+            if remaining <= 0.0 || total <= 0.0 {
+                return 0.0;
+            }
+
+            elapsed = remaining % total;
+        }
+    }
+
+    /// Returns true if the value is still changing.
+    pub fn is_running(&self) -> bool {
+        self.looping || self.steps[self.current].is_running()
+    }
+
+    /// Goes back to the start of the first tween.
+    pub fn restart(&mut self) {
+        for step in self.steps.iter_mut() {
+            step.restart();
+        }
+
+        self.current = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Easing, Repeat, Sequence, Tween};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn tween_timing_and_delay() {
+        let mut tween = Tween::new(0.0, 10.0, 2.0);
+        tween.delay = 1.0;
+        assert!(tween.is_running());
+
+        assert_eq!(tween.advance(0.5), 0.0);
+        assert_eq!(tween.get(), 0.0);
+        assert!(tween.is_running());
+
+        assert_eq!(tween.advance(1.5), 0.0);
+        assert!(close(tween.get_progress(), 0.5));
+        assert!(close(tween.get(), 5.0));
+
+        // the extra time is returned and the value stays at the end
+        assert!(close(tween.advance(1.5), 0.5));
+        assert_eq!(tween.get(), 10.0);
+        assert!(!tween.is_running());
+        assert_eq!(tween.advance(1.0), 1.0);
+
+        tween.restart();
+        assert_eq!(tween.get(), 0.0);
+        assert!(tween.is_running());
+    }
+
+    #[test]
+    fn retarget_starts_from_the_current_value() {
+        let mut tween = Tween::new(0.0, 10.0, 2.0);
+        tween.advance(1.0);
+        tween.retarget(20.0);
+        assert!(close(tween.get(), 5.0));
+        tween.advance(1.0);
+        assert!(close(tween.get(), 12.5));
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        for &easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut,
+                        Easing::Overshoot].iter()
+        {
+            assert!(close(easing.apply(0.0), 0.0), "{:?}", easing);
+            assert!(close(easing.apply(1.0), 1.0), "{:?}", easing);
+        }
+
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!(close(Easing::EaseInOut.apply(0.5), 0.5));
+        assert!(Easing::Overshoot.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn loop_and_ping_pong() {
+        let mut looping = Tween::new(0.0, 10.0, 2.0);
+        looping.repeat = Repeat::Loop;
+        let mut ping_pong = looping.clone();
+        ping_pong.repeat = Repeat::PingPong;
+
+        assert_eq!(looping.advance(2.5), 0.0);
+        assert_eq!(ping_pong.advance(2.5), 0.0);
+        assert!(close(looping.get(), 2.5));
+        assert!(close(ping_pong.get(), 7.5));
+
+        looping.advance(100.0);
+        ping_pong.advance(100.0);
+        assert!(close(looping.get(), 2.5));
+        assert!(close(ping_pong.get(), 7.5));
+        assert!(looping.is_running());
+        assert!(ping_pong.is_running());
+    }
+
+    #[test]
+    fn sequence_hands_the_leftover_time_over() {
+        let mut sequence = Sequence::new(vec![
+            Tween::new(0.0, 10.0, 1.0),
+            Tween::hold(10.0, 1.0),
+            Tween::new(10.0, 0.0, 2.0),
+        ]);
+
+        assert_eq!(sequence.advance(0.5), 0.0);
+        assert_eq!(sequence.get_current_step(), 0);
+        assert!(close(sequence.get(), 5.0));
+
+        // goes through the end of the first tween and the whole pause
+        assert_eq!(sequence.advance(2.0), 0.0);
+        assert_eq!(sequence.get_current_step(), 2);
+        assert!(close(sequence.get(), 7.5));
+
+        assert!(close(sequence.advance(2.0), 0.5));
+        assert_eq!(sequence.get(), 0.0);
+        assert!(!sequence.is_running());
+    }
+
+    #[test]
+    fn looping_sequence() {
+        let mut sequence = Sequence::looping(vec![
+            Tween::new(0.0, 10.0, 1.0),
+            Tween::new(10.0, 0.0, 1.0),
+        ]);
+
+        assert_eq!(sequence.advance(4.25), 0.0);
+        assert_eq!(sequence.get_current_step(), 0);
+        assert!(close(sequence.get(), 2.5));
+
+        assert_eq!(sequence.advance(1.0), 0.0);
+        assert_eq!(sequence.get_current_step(), 1);
+        assert!(close(sequence.get(), 7.5));
+        assert!(sequence.is_running());
+    }
+}
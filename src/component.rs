@@ -1,24 +1,348 @@
 use std::any::Any;
+use std::f32;
 use nalgebra::Vec2;
 
+use drag::{DragEvent, DragStart};
 use shape::Shape;
 
 /// Represents a raw component. Don't implement this directly.
+///
+/// The parent of a component first asks it which size it wants with `get_size`, giving it
+/// constraints. Then all the other methods that need to know where the children of the
+/// component are receive the size that the parent chose for it.
 pub trait RawComponent<E> {
-    /// Obtain the look of this component.
-    fn render(&mut self) -> Vec<Shape>;
+    /// Obtain the look of this component, given its size.
+    fn render(&mut self, size: Vec2<f32>) -> Vec<Shape>;
 
-    /// Tells the component where the mouse is. `None` if the mouse is not over the element.
+    /// Tells the component where a pointer is. `None` if the pointer is not over the element.
+    ///
+    /// Each pointer is reported independently. `pressed` is the status of the left button of
+    /// the mouse, or whether the finger or the pen touches the screen.
+    ///
+    /// Returns a list of events to pass to the parent.
+    fn set_pointer_status(&mut self, size: Vec2<f32>, PointerId, Option<Vec2<f32>>,
+                          pressed: bool) -> Vec<E>;
+
+    /// Asks the component of the given size whether the given position touches it.
+    fn hit_test(&mut self, size: Vec2<f32>, Vec2<f32>) -> bool;
+
+    /// Returns the size that the component wants, which must respect the constraints.
+    ///
+    /// If the size returned for some constraints also respects tighter constraints, the component
+    /// must return the same size for the tighter ones. The layouts rely on this to avoid
+    /// measuring a child twice, so a component must not take a fraction of the space that it is
+    /// offered.
+    fn get_size(&mut self, &BoxConstraints) -> Vec2<f32>;
+
+    /// Returns the space that the parent's layout must leave around the component.
+    ///
+    /// The size of the component doesn't include it. The default is no space.
+    fn get_margin(&mut self) -> Insets {
+        Default::default()
+    }
+
+    /// Tells the component where a pointer captured by one of its descendants is, regardless of
+    /// whether the pointer is over the element.
+    ///
+    /// The capture belongs to the descendant at the given path of child indices. An empty path
+    /// designates this component. The default implementation treats the position like
+    /// `set_pointer_status` does.
+    ///
+    /// Returns a list of events to pass to the parent.
+    fn set_captured_pointer_status(&mut self, size: Vec2<f32>, path: &[usize],
+                                   pointer: PointerId, position: Vec2<f32>, pressed: bool)
+                                   -> Vec<E>
+    {
+        if path.is_empty() {
+            self.set_pointer_status(size, pointer, Some(position), pressed)
+        } else {
+            self.set_pointer_status(size, pointer, None, pressed)
+        }
+    }
+
+    /// A button of a pointer has just been pressed over the descendant at the given path of
+    /// child indices. Returns the path of the component that wants to capture the pointer, if
+    /// any. An empty path designates this component.
+    ///
+    /// Once captured, the pointer is reported with `set_captured_pointer_status` until all its
+    /// buttons are released. Components implementing this trait directly can return
+    /// `Some(Vec::new())` to capture every pointer pressed over them.
+    fn get_capture_path(&mut self, &[usize], PointerId, MouseButton) -> Option<Vec<usize>> {
+        None
+    }
+
+    /// A pointer pressed over the descendant at the given path of child indices has moved far
+    /// enough to start a drag. Returns the path of the component that starts a drag, if any,
+    /// alongside the description of the drag. An empty path designates this component.
+    fn get_drag_start(&mut self, &[usize], PointerId) -> Option<(Vec<usize>, DragStart)> {
+        None
+    }
+
+    /// A drag is over the descendant at the given path of child indices. Returns the path of
+    /// the component that accepts the payload, if any. An empty path designates this component.
+    fn get_drop_target(&mut self, &[usize], &Any) -> Option<Vec<usize>> {
+        None
+    }
+
+    /// Tells the descendant at the given path of child indices that something happened to a
+    /// drag while the pointer was at the given position. An empty path designates this
+    /// component.
+    ///
+    /// Returns a list of events to pass to the parent.
+    fn drag_event(&mut self, size: Vec2<f32>, &[usize], Vec2<f32>, DragEvent) -> Vec<E> {
+        Vec::with_capacity(0)
+    }
+
+    /// Tells the component that something happened with a button of a pointer at the given
+    /// position.
+    ///
+    /// The event is destined to the descendant at the given path of child indices, and to all
+    /// the components in between. An empty path designates this component.
+    ///
+    /// Returns a list of events to pass to the parent.
+    fn pointer_event(&mut self, size: Vec2<f32>, &[usize], PointerId, Vec2<f32>, PointerEvent)
+                     -> Vec<E>
+    {
+        Vec::with_capacity(0)
+    }
+
+    /// Tells the component which of its descendants are the deepest components under each
+    /// pointer. `old` contains the paths of child indices given during the previous call, and
+    /// `new` the current ones. An empty path designates this component.
+    ///
+    /// Returns a list of events to pass to the parent.
+    fn set_hovered_paths(&mut self, old: &[&[usize]], new: &[&[usize]]) -> Vec<E> {
+        Vec::with_capacity(0)
+    }
+
+    /// Returns the path of child indices leading to the deepest component under the given
+    /// position, or `None` if the position doesn't touch this component. An empty path
+    /// designates this component.
+    fn hit_path(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> Option<Vec<usize>> {
+        if self.hit_test(size, position) {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+
+    /// Same as `hit_path`, but also returns what `render` returns. The default implementation
+    /// calls both.
+    ///
+    /// Components whose bounding box comes from their shapes implement it so that finding the
+    /// deepest component renders each component once, instead of once per ancestor.
+    fn render_hit_path(&mut self, size: Vec2<f32>, position: Vec2<f32>)
+                       -> (Vec<Shape>, Option<Vec<usize>>)
+    {
+        let path = self.hit_path(size, position);
+        (self.render(size), path)
+    }
+
+    /// Tells the component that the mouse wheel was used while the mouse was at the given
+    /// position.
+    ///
+    /// Returns whether the scroll was handled, and a list of events to pass to the parent. An
+    /// unhandled scroll is offered to the parent.
+    fn scroll(&mut self, size: Vec2<f32>, Vec2<f32>, ScrollDelta) -> (bool, Vec<E>) {
+        (false, Vec::with_capacity(0))
+    }
+
+    /// Sends a character typed on the keyboard to the component. `None` if the mouse is not over
+    /// the element.
     ///
     /// Returns a list of events to pass to the parent.
-    fn set_mouse_status(&mut self, Option<Vec2<f32>>, pressed: bool) -> Vec<E>;
+    fn send_character(&mut self, size: Vec2<f32>, Option<Vec2<f32>>, char) -> Vec<E> {
+        Vec::with_capacity(0)
+    }
 
-    /// Asks the component whether the given position touches it.
-    fn hit_test(&mut self, Vec2<f32>) -> bool;
+    /// Returns the path of child indices leading to the component with the given test id, or
+    /// `None` if there is no such component. An empty path designates this component.
+    fn find_test_id(&mut self, &str) -> Option<Vec<usize>> {
+        None
+    }
 
-    /// Returns the width of the component.
-    fn get_width(&mut self) -> f32;
+    /// Advances the time of this component and of its children by the given number of seconds.
+    ///
+    /// The default action is not to do anything.
+    fn tick(&mut self, elapsed: f32) -> Vec<E> {
+        Vec::with_capacity(0)
+    }
+
+    /// Returns true if this component or one of its children is animating and needs to be
+    /// ticked and redrawn even when there is no input. The default is `false`.
+    fn is_animating(&mut self) -> bool {
+        false
+    }
+
+    /// Returns the bottom-left and top-right corners of the area covered by the component at
+    /// the given path of child indices, relative to this component.
+    ///
+    /// An empty path designates this component.
+    fn get_child_area(&mut self, size: Vec2<f32>, path: &[usize])
+                      -> Option<(Vec2<f32>, Vec2<f32>)>
+    {
+        if path.is_empty() {
+            Some((Vec2::new(0.0, 0.0), size))
+        } else {
+            None
+        }
+    }
+}
+
+/// Minimum and maximum sizes that a parent allows for a component.
+///
+/// The maximum can be infinite, for example along the axis of a `HorizontalBox`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxConstraints {
+    /// Smallest size allowed.
+    pub min: Vec2<f32>,
+    /// Largest size allowed. Can be infinite.
+    pub max: Vec2<f32>,
+}
+
+impl BoxConstraints {
+    /// Constraints that only allow the given size.
+    pub fn tight(size: Vec2<f32>) -> BoxConstraints {
+        BoxConstraints { min: size, max: size }
+    }
+
+    /// Constraints that allow any size up to the given one.
+    pub fn loose(max: Vec2<f32>) -> BoxConstraints {
+        BoxConstraints { min: Vec2::new(0.0, 0.0), max: max }
+    }
+
+    /// Constraints that allow any size.
+    pub fn unbounded() -> BoxConstraints {
+        BoxConstraints::loose(Vec2::new(f32::INFINITY, f32::INFINITY))
+    }
+
+    /// Returns the size that respects the constraints and that is the closest to the given one.
+    pub fn constrain(&self, size: Vec2<f32>) -> Vec2<f32> {
+        Vec2::new(size.x.max(self.min.x).min(self.max.x), size.y.max(self.min.y).min(self.max.y))
+    }
+
+    /// Returns the same constraints without a minimum.
+    pub fn loosen(&self) -> BoxConstraints {
+        BoxConstraints::loose(self.max)
+    }
+
+    /// Returns the constraints that apply to an area smaller by the given amount, for example
+    /// the inside of a padding.
+    pub fn deflate(&self, amount: Vec2<f32>) -> BoxConstraints {
+        BoxConstraints {
+            min: Vec2::new((self.min.x - amount.x).max(0.0), (self.min.y - amount.y).max(0.0)),
+            max: Vec2::new((self.max.x - amount.x).max(0.0), (self.max.y - amount.y).max(0.0)),
+        }
+    }
+
+    /// Returns true if the maximum width is finite.
+    pub fn has_bounded_width(&self) -> bool {
+        self.max.x.is_finite()
+    }
+
+    /// Returns true if the maximum height is finite.
+    pub fn has_bounded_height(&self) -> bool {
+        self.max.y.is_finite()
+    }
+}
+
+/// Space around the four sides of a rectangle.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Insets {
+    /// Space on the left side.
+    pub left: f32,
+    /// Space on the right side.
+    pub right: f32,
+    /// Space on the bottom side.
+    pub bottom: f32,
+    /// Space on the top side.
+    pub top: f32,
+}
+
+impl Insets {
+    /// Builds insets with the same space on every side.
+    pub fn uniform(value: f32) -> Insets {
+        Insets::symmetric(value, value)
+    }
+
+    /// Builds insets with the same space on the left and right, and on the bottom and top.
+    pub fn symmetric(horizontal: f32, vertical: f32) -> Insets {
+        Insets {
+            left: horizontal,
+            right: horizontal,
+            bottom: vertical,
+            top: vertical,
+        }
+    }
+
+    /// Returns the position of the inner area relative to the outer one.
+    pub fn get_offset(&self) -> Vec2<f32> {
+        Vec2::new(self.left, self.bottom)
+    }
+
+    /// Returns the sum of the horizontal and of the vertical spaces.
+    pub fn get_total(&self) -> Vec2<f32> {
+        Vec2::new(self.left + self.right, self.bottom + self.top)
+    }
+}
+
+/// Amount of scrolling of a mouse wheel or touchpad.
+///
+/// Positive values of `y` mean that the content should move up, and positive values of `x` that
+/// it should move right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDelta {
+    /// Number of lines, as reported by regular mouse wheels.
+    Lines(Vec2<f32>),
+
+    /// Number of pixels, as reported by touchpads.
+    ///
+    /// `Ui::scroll` turns this into `Logical` before passing it to the components.
+    Pixels(Vec2<f32>),
+
+    /// Distance in the unit of the components. See `Ui::set_unit`.
+    Logical(Vec2<f32>),
+}
+
+/// Identifies a pointer over the user interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointerId {
+    /// The mouse.
+    Mouse,
+    /// A finger on a touch screen, with an identifier provided by the system.
+    Touch(u64),
+    /// A pen on a tablet, with an identifier provided by the system.
+    Pen(u64),
+}
+
+/// A button of the mouse.
+///
+/// Fingers and pens only have a `Left` button, which is pressed while they touch the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    /// The left button.
+    Left,
+    /// The right button.
+    Right,
+    /// The middle button, often the wheel.
+    Middle,
+}
 
-    /// Returns the height of the component.
-    fn get_height(&mut self) -> f32;
+/// Something that happened with a button of a pointer.
+///
+/// Clicks, double clicks and long presses are detected by the `Ui` according to its
+/// `ClickSettings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerEvent {
+    /// The button has been pressed.
+    Pressed(MouseButton),
+    /// The button has been released.
+    Released(MouseButton),
+    /// The button has been pressed then released without the mouse moving much.
+    Click(MouseButton),
+    /// Second click in a short amount of time. Always follows a `Click`.
+    DoubleClick(MouseButton),
+    /// The button has been held for a long time without the mouse moving much. A `LongPress` is
+    /// not followed by a `Click` when the button is released.
+    LongPress(MouseButton),
 }
@@ -0,0 +1,210 @@
+//! Types used for dragging and dropping between components.
+//!
+//! A drag starts when a pointer moves far enough after a button was pressed over a component
+//! whose `Component::start_drag` returns a `DragStart`. While the drag goes on, the shapes of
+//! `DragStart::visual` follow the pointer, and the deepest component under the pointer that
+//! accepts the payload in `Component::accepts_drop` is the target of the drag.
+
+use std::any::Any;
+use std::fmt;
+
+use nalgebra::Vec2;
+
+use shape::Shape;
+
+/// Describes a drag that a component wants to start.
+pub struct DragStart {
+    /// The data being dragged. Given to the target on drop.
+    pub payload: Box<Any>,
+
+    /// Shapes that follow the pointer while the drag goes on. `(0.0, 0.0)` corresponds to the
+    /// position of the pointer.
+    pub visual: Vec<Shape>,
+}
+
+/// Something that happened to a drag.
+pub enum DragEvent<'a> {
+    /// The drag entered this component, which accepted its payload.
+    Enter(&'a Any),
+
+    /// The drag moved over this component. Always follows `Enter`.
+    Over(&'a Any),
+
+    /// The drag left this component, or was cancelled while over it.
+    Leave,
+
+    /// The payload was dropped on this component.
+    Drop(Box<Any>),
+
+    /// The drag started by this component ended over a target that received the payload.
+    Dropped,
+
+    /// The drag started by this component ended without any target.
+    Cancelled,
+}
+
+impl<'a> fmt::Debug for DragEvent<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &DragEvent::Enter(_) => write!(f, "Enter"),
+            &DragEvent::Over(_) => write!(f, "Over"),
+            &DragEvent::Leave => write!(f, "Leave"),
+            &DragEvent::Drop(_) => write!(f, "Drop"),
+            &DragEvent::Dropped => write!(f, "Dropped"),
+            &DragEvent::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+/// A drag in progress.
+pub struct Drag {
+    /// Path of the component that started the drag.
+    pub source: Vec<usize>,
+    /// The data being dragged.
+    pub payload: Box<Any>,
+    /// Shapes that follow the pointer.
+    pub visual: Vec<Shape>,
+    /// Path of the component that accepted the payload and is under the pointer.
+    pub target: Option<Vec<usize>>,
+    /// Last known position of the pointer, relative to the main component.
+    pub position: Vec2<f32>,
+}
+
+impl Drag {
+    /// Returns the shapes that represent the drag at the position of the pointer.
+    pub fn render(&self) -> Vec<Shape> {
+        self.visual.iter().map(|s| s.clone().translate(self.position)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use nalgebra::Vec2;
+
+    use Component;
+    use Layout;
+    use PositionnedChild;
+    use component::PointerId;
+    use shape::Shape;
+    use ui::Ui;
+    use super::{DragEvent, DragStart};
+
+    /// A square of 100 by 100 that starts dragging a `u32` or accepts one, and describes its
+    /// drag events.
+    struct Square {
+        source: bool,
+    }
+
+    impl Component for Square {
+        type EmittedEvent = String;
+        type ReceivedEvent = String;
+
+        fn get_layout(&mut self) -> Layout<String> {
+            Layout::PositionnedChildren(Vec::new(), None)
+        }
+
+        fn get_dimensions(&self) -> Option<Vec2<f32>> {
+            Some(Vec2::new(100.0, 100.0))
+        }
+
+        fn get_bounding_box(&self) -> Option<(Vec2<f32>, Vec2<f32>)> {
+            Some((Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0)))
+        }
+
+        fn start_drag(&mut self, _: PointerId) -> Option<DragStart> {
+            if !self.source {
+                return None;
+            }
+
+            Some(DragStart {
+                payload: Box::new(7u32),
+                visual: vec![Shape::Rectangle {
+                    from: Vec2::new(-5.0, -5.0),
+                    to: Vec2::new(5.0, 5.0),
+                    color: [1.0, 1.0, 1.0],
+                }],
+            })
+        }
+
+        fn accepts_drop(&mut self, payload: &Any) -> bool {
+            !self.source && payload.is::<u32>()
+        }
+
+        fn handle_drag_event(&mut self, position: Vec2<f32>, event: DragEvent) -> Option<String> {
+            let event = match event {
+                DragEvent::Drop(payload) => format!("Drop {}", payload.downcast::<u32>().unwrap()),
+                event => format!("{:?}", event),
+            };
+
+            Some(format!("{} at {} {}", event, position.x, position.y))
+        }
+    }
+
+    /// A source at `(0, 0)` and a target at `(200, 0)`.
+    struct Board {
+        source: Square,
+        target: Square,
+    }
+
+    impl Component for Board {
+        type EmittedEvent = String;
+        type ReceivedEvent = String;
+
+        fn get_layout(&mut self) -> Layout<String> {
+            Layout::PositionnedChildren(vec![
+                PositionnedChild::new(&mut self.source, 0.0, 0.0),
+                PositionnedChild::new(&mut self.target, 200.0, 0.0),
+            ], None)
+        }
+
+        fn handle_child_event(&mut self, child_id: usize, event: String) -> Option<String> {
+            Some(format!("{} {}", child_id, event))
+        }
+    }
+
+    fn board_ui() -> Ui<Board, String> {
+        let board = Board { source: Square { source: true }, target: Square { source: false } };
+        Ui::new(board, Vec2::new(800, 600))
+    }
+
+    #[test]
+    fn drop_on_target() {
+        let mut ui = board_ui();
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+        ui.set_mouse_pressed(true);
+        assert!(ui.get_drag(PointerId::Mouse).is_none());
+
+        // the visual follows the pointer, which is not over any target yet
+        ui.set_mouse_position(Some(Vec2::new(150, 550)));
+        assert!(ui.poll_events().is_empty());
+        assert_eq!(ui.get_drag(PointerId::Mouse).unwrap().position, Vec2::new(150.0, 50.0));
+        assert_eq!(ui.draw().len(), 1);
+
+        ui.set_mouse_position(Some(Vec2::new(250, 550)));
+        assert_eq!(ui.poll_events(), vec!["1 Enter at 50 50", "1 Over at 50 50"]);
+        ui.set_mouse_position(Some(Vec2::new(260, 540)));
+        assert_eq!(ui.poll_events(), vec!["1 Over at 60 60"]);
+
+        ui.set_mouse_pressed(false);
+        assert_eq!(ui.poll_events(), vec!["1 Drop 7 at 60 60", "0 Dropped at 260 60"]);
+        assert!(ui.get_drag(PointerId::Mouse).is_none());
+        assert!(ui.draw().is_empty());
+    }
+
+    #[test]
+    fn drop_on_nothing() {
+        let mut ui = board_ui();
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+        ui.set_mouse_pressed(true);
+        ui.set_mouse_position(Some(Vec2::new(250, 550)));
+        assert_eq!(ui.poll_events(), vec!["1 Enter at 50 50", "1 Over at 50 50"]);
+
+        ui.set_mouse_position(Some(Vec2::new(500, 300)));
+        assert_eq!(ui.poll_events(), vec!["1 Leave at 300 300"]);
+
+        ui.set_mouse_pressed(false);
+        assert_eq!(ui.poll_events(), vec!["0 Cancelled at 500 300"]);
+    }
+}
@@ -1,7 +1,12 @@
+use std::any::Any;
+use std::f32;
 use nalgebra::Vec2;
 
-use component::RawComponent;
+use drag::{DragEvent, DragStart};
+use component::{BoxConstraints, Insets, MouseButton, PointerEvent, PointerId, RawComponent};
+use component::ScrollDelta;
 use Shape;
+use Transform;
 
 pub trait Component {
     /// What events type this component produces.
@@ -20,21 +25,95 @@ pub trait Component {
         None
     }
 
-    /// Sets whether this component is hovered by the mouse or not.
+    /// Something happened with a button of a pointer over this component. Called after the
+    /// child under the pointer had the event delivered.
     ///
     /// The default action is not to do anything.
-    fn set_hovered_status(&self, HoveredStatus) -> Option<Self::EmittedEvent> { None }
+    fn handle_pointer_event(&mut self, PointerId, PointerEvent) -> Option<Self::EmittedEvent> {
+        None
+    }
 
-    /// Returns the dimensions of the component. If returns `None`, the dimensions are
-    /// automatically calculated using what `render` returns.
+    /// A button of a pointer has been pressed over this component, and none of the children
+    /// under the pointer captured it. Returns whether this component wants to capture it.
+    ///
+    /// A captured pointer keeps being reported to `handle_captured_pointer_move` even when it
+    /// leaves the component, until all its buttons are released. The default action is not to
+    /// capture it.
+    fn wants_pointer_capture(&mut self, PointerId, MouseButton) -> bool {
+        false
+    }
+
+    /// A pointer captured by this component has moved. The position is relative to the
+    /// component and can be outside of it.
+    ///
+    /// The default action is not to do anything.
+    fn handle_captured_pointer_move(&mut self, PointerId, Vec2<f32>, pressed: bool)
+                                    -> Option<Self::EmittedEvent>
+    {
+        None
+    }
+
+    /// A pointer pressed over this component has moved far enough to start a drag, and none of
+    /// the children under it started one. Returns the drag to start, if any.
     ///
-    /// If `render` only returns shapes, then your component will have a dimension of `(0.0, 0.0)`.
+    /// The default action is not to start any drag.
+    fn start_drag(&mut self, PointerId) -> Option<DragStart> {
+        None
+    }
+
+    /// A drag is over this component and none of the children under it accepted it. Returns
+    /// whether this component accepts to receive the payload.
+    ///
+    /// The default action is to reject it.
+    fn accepts_drop(&mut self, &Any) -> bool {
+        false
+    }
+
+    /// Something happened to a drag that this component started or accepted. The position of
+    /// the pointer is relative to the component.
+    ///
+    /// The default action is not to do anything.
+    fn handle_drag_event(&mut self, Vec2<f32>, DragEvent) -> Option<Self::EmittedEvent> {
+        None
+    }
+
+    /// The mouse wheel was used over this component and none of its children handled it.
+    ///
+    /// Returns whether the scroll was handled. Unhandled scrolls are offered to the parent. The
+    /// default action is to not handle it.
+    fn handle_scroll(&mut self, ScrollDelta) -> (bool, Option<Self::EmittedEvent>) {
+        (false, None)
+    }
+
+    /// Sets whether this component is hovered by the mouse or not.
+    ///
+    /// Only called when the status changes. The default action is not to do anything.
+    fn set_hovered_status(&mut self, HoveredStatus) -> Option<Self::EmittedEvent> { None }
+
+    /// Returns the dimensions of the component. If returns `None`, the dimensions are
+    /// automatically calculated from the layout.
     ///
-    /// The dimensions are used when calculating layouts.
+    /// The dimensions are used when calculating layouts, within the constraints given by the
+    /// parent.
     fn get_dimensions(&self) -> Option<Vec2<f32>> {
         None
     }
 
+    /// Returns the space between the borders of the component and its layout.
+    ///
+    /// The padding is part of the component: it counts in its dimensions and hovering it hovers
+    /// the component. The default is no padding.
+    fn get_padding(&self) -> Insets {
+        Default::default()
+    }
+
+    /// Returns the space that the parent's layout must leave around the component.
+    ///
+    /// The default is no margin.
+    fn get_margin(&self) -> Insets {
+        Default::default()
+    }
+
     /// Returns the bounding box of the component. If returns `None`, the bounding box corresponds
     /// to the bounding boxes of what `render` returns. The default behavior of this function is
     /// to return `None`, and it is what you should usually do.
@@ -43,175 +122,701 @@ pub trait Component {
     fn get_bounding_box(&self) -> Option<(Vec2<f32>, Vec2<f32>)> {
         None
     }
+
+    /// Returns true if the children must only be drawn and touched within the area of the
+    /// component. The default is `false`.
+    ///
+    /// The shapes of the children are then wrapped inside `Shape::Clipped`.
+    fn clips_children(&self) -> bool {
+        false
+    }
+
+    /// Returns an identifier that tests can use to locate this component, regardless of where
+    /// the layout places it.
+    fn get_test_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// Time has passed. Called after the children had their time advanced.
+    ///
+    /// This is where the component advances its tweens. The default action is not to do
+    /// anything.
+    fn tick(&mut self, elapsed: f32) -> Option<Self::EmittedEvent> {
+        None
+    }
+
+    /// Returns true if the component is animating and must keep being ticked and redrawn, for
+    /// example while one of its tweens is running. The default is `false`.
+    fn is_animating(&self) -> bool {
+        false
+    }
 }
 
 pub enum Layout<'a, E> {
     SingleChild(&'a mut RawComponent<E>),
 
-    HorizontalBox(Vec<&'a mut RawComponent<E>>),
+    /// Children placed from left to right, separated by the given spacing.
+    HorizontalBox(Vec<&'a mut RawComponent<E>>, f32),
+
+    /// Children placed from bottom to top, separated by the given spacing.
+    VerticalBox(Vec<&'a mut RawComponent<E>>, f32),
+
+    /// Children placed at arbitrary positions. The size of the layout is the one given, or the
+    /// extent of the children if `None`. In the latter case, the children are moved so that the
+    /// leftmost and the bottommost ones touch the borders of the layout, and negative positions
+    /// don't overflow. This isn't done along the axes where a child has an anchor or a relative
+    /// size, as these children are placed relatively to the borders.
+    ///
+    /// The area to which the anchors and relative sizes of the children refer is the given
+    /// size, or else the maximum size allowed by the constraints.
+    PositionnedChildren(Vec<PositionnedChild<'a, E>>, Option<Vec2<f32>>),
+
+    /// Children placed one after the other along an axis and sharing the available space.
+    Flex(Flex<'a, E>),
+
+    /// Children placed in the cells of a table.
+    Grid(Grid<'a, E>),
 
-    VerticalBox(Vec<&'a mut RawComponent<E>>),
+    /// Children placed on top of each other, the last one being the topmost. The size of the
+    /// layout is the one given, or the size of the largest child if `None`.
+    Stack(Vec<StackChild<'a, E>>, Option<Vec2<f32>>),
 
-    PositionnedChildren(Vec<PositionnedChild<'a, E>>),
+    /// A single child drawn with a transformation, for example rotated. The size of the layout
+    /// is the size of the child before the transformation.
+    Transformed(TransformedChild<'a, E>),
 }
 
 pub struct PositionnedChild<'a, E> {
     pub child: &'a mut RawComponent<E>,
+    /// Horizontal distance between the anchor and the pivot.
     pub x: f32,
+    /// Vertical distance between the anchor and the pivot.
     pub y: f32,
+    /// Point of the area of the parent from which the child is positionned, as a fraction of
+    /// this area. `(0.0, 0.0)` is the bottom-left corner and `(1.0, 1.0)` the top-right corner.
+    pub anchor: Vec2<f32>,
+    /// Point of the child that is placed at the position, as a fraction of the size of the
+    /// child. `(0.0, 0.0)` is the bottom-left corner and `(1.0, 1.0)` the top-right corner.
+    pub pivot: Vec2<f32>,
+    /// If `Some`, the width of the child is this fraction of the width of the area of the
+    /// parent.
+    pub relative_width: Option<f32>,
+    /// If `Some`, the height of the child is this fraction of the height of the area of the
+    /// parent.
+    pub relative_height: Option<f32>,
+}
+
+impl<'a, E> PositionnedChild<'a, E> {
+    /// Builds a child whose bottom-left corner is at the given position relative to the
+    /// bottom-left corner of the parent.
+    pub fn new(child: &'a mut RawComponent<E>, x: f32, y: f32) -> PositionnedChild<'a, E> {
+        PositionnedChild {
+            child: child,
+            x: x,
+            y: y,
+            anchor: Vec2::new(0.0, 0.0),
+            pivot: Vec2::new(0.0, 0.0),
+            relative_width: None,
+            relative_height: None,
+        }
+    }
+
+    /// Builds a child pinned to a point of the parent, with an offset.
+    ///
+    /// For example `Anchor::TopRight` places the top-right corner of the child at the top-right
+    /// corner of the parent, moved by `x` and `y`.
+    pub fn anchored(child: &'a mut RawComponent<E>, anchor: Anchor, x: f32, y: f32)
+                    -> PositionnedChild<'a, E>
+    {
+        let point = anchor.get_fraction();
+
+        PositionnedChild {
+            anchor: point,
+            pivot: point,
+            .. PositionnedChild::new(child, x, y)
+        }
+    }
+}
+
+/// A remarkable point of a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    Left,
+    Center,
+    Right,
+    TopLeft,
+    Top,
+    TopRight,
+}
+
+impl Anchor {
+    /// Returns the position of the point as a fraction of the size of the rectangle.
+    pub fn get_fraction(&self) -> Vec2<f32> {
+        match *self {
+            Anchor::BottomLeft => Vec2::new(0.0, 0.0),
+            Anchor::Bottom => Vec2::new(0.5, 0.0),
+            Anchor::BottomRight => Vec2::new(1.0, 0.0),
+            Anchor::Left => Vec2::new(0.0, 0.5),
+            Anchor::Center => Vec2::new(0.5, 0.5),
+            Anchor::Right => Vec2::new(1.0, 0.5),
+            Anchor::TopLeft => Vec2::new(0.0, 1.0),
+            Anchor::Top => Vec2::new(0.5, 1.0),
+            Anchor::TopRight => Vec2::new(1.0, 1.0),
+        }
+    }
+}
+
+/// Children placed one after the other along an axis, similar to CSS flexboxes.
+pub struct Flex<'a, E> {
+    /// Axis along which the children are placed.
+    pub direction: FlexDirection,
+    /// How the children are distributed along the main axis.
+    pub justify: Justify,
+    /// How the children are placed along the cross axis.
+    pub align: Align,
+    /// Space between two consecutive children.
+    pub gap: f32,
+    /// Size of the layout. If `None`, the layout takes all the space allowed by its constraints
+    /// along the main axis, and along the cross axis if the children are stretched. Unbounded
+    /// axes take the natural size of the children.
+    pub size: Option<Vec2<f32>>,
+    /// The children.
+    pub children: Vec<FlexChild<'a, E>>,
+}
+
+pub struct FlexChild<'a, E> {
+    pub child: &'a mut RawComponent<E>,
+    /// Share of the remaining space that this child receives when there is more space than
+    /// needed.
+    pub grow: f32,
+    /// How much this child shrinks, relative to the others and to its size, when there is not
+    /// enough space.
+    pub shrink: f32,
+}
+
+impl<'a, E> FlexChild<'a, E> {
+    /// Builds a child that doesn't grow and that shrinks like the others.
+    pub fn new(child: &'a mut RawComponent<E>) -> FlexChild<'a, E> {
+        FlexChild {
+            child: child,
+            grow: 0.0,
+            shrink: 1.0,
+        }
+    }
+}
+
+/// Axis along which the children of a `Flex` are placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlexDirection {
+    /// From left to right.
+    Horizontal,
+    /// From bottom to top, like `VerticalBox`.
+    Vertical,
+}
+
+/// How children are distributed along the main axis of a layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Justify {
+    /// Packed at the start.
+    Start,
+    /// Packed in the middle.
+    Center,
+    /// Packed at the end.
+    End,
+    /// The first child at the start, the last one at the end and the remaining space split
+    /// between the others.
+    SpaceBetween,
+}
+
+/// How a child is placed in the space available to it along an axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// At the start of the space.
+    Start,
+    /// In the middle of the space.
+    Center,
+    /// At the end of the space.
+    End,
+    /// The child is given the whole space.
+    Stretch,
+}
+
+impl Align {
+    /// Returns the position of a child of the given size within the available space, and the
+    /// size of the space given to it.
+    pub fn place(&self, size: f32, available: f32) -> (f32, f32) {
+        match *self {
+            Align::Start => (0.0, size),
+            Align::Center => ((available - size) / 2.0, size),
+            Align::End => (available - size, size),
+            Align::Stretch => (0.0, available),
+        }
+    }
+}
+
+pub struct StackChild<'a, E> {
+    pub child: &'a mut RawComponent<E>,
+    /// How the child is placed horizontally in the area of the stack.
+    pub horizontal_align: Align,
+    /// How the child is placed vertically in the area of the stack.
+    pub vertical_align: Align,
+}
+
+impl<'a, E> StackChild<'a, E> {
+    /// Builds a child placed with the given alignments.
+    pub fn new(child: &'a mut RawComponent<E>, horizontal_align: Align, vertical_align: Align)
+               -> StackChild<'a, E>
+    {
+        StackChild {
+            child: child,
+            horizontal_align: horizontal_align,
+            vertical_align: vertical_align,
+        }
+    }
+
+    /// Builds a child that covers the whole area of the stack.
+    pub fn fill(child: &'a mut RawComponent<E>) -> StackChild<'a, E> {
+        StackChild::new(child, Align::Stretch, Align::Stretch)
+    }
+
+    /// Builds a child placed in the middle of the stack.
+    pub fn center(child: &'a mut RawComponent<E>) -> StackChild<'a, E> {
+        StackChild::new(child, Align::Center, Align::Center)
+    }
+}
+
+pub struct TransformedChild<'a, E> {
+    pub child: &'a mut RawComponent<E>,
+    /// Transformation applied to the child, for example a rotation or a scale.
+    pub transform: Transform,
+    /// Point around which the transformation is applied, as a fraction of the size of the
+    /// child. `(0.5, 0.5)` is the middle of the child.
+    pub pivot: Vec2<f32>,
+}
+
+impl<'a, E> TransformedChild<'a, E> {
+    /// Builds a child transformed around its middle.
+    pub fn new(child: &'a mut RawComponent<E>, transform: Transform) -> TransformedChild<'a, E> {
+        TransformedChild {
+            child: child,
+            transform: transform,
+            pivot: Vec2::new(0.5, 0.5),
+        }
+    }
+}
+
+/// Children placed in the cells of a table.
+///
+/// Columns are counted from the left and rows from the bottom, like the children of
+/// `VerticalBox`.
+pub struct Grid<'a, E> {
+    /// Widths of the columns. Children placed after the last column create `Auto` columns.
+    pub columns: Vec<Track>,
+    /// Heights of the rows. Children placed after the last row create `Auto` rows.
+    pub rows: Vec<Track>,
+    /// Space between two consecutive columns.
+    pub column_gap: f32,
+    /// Space between two consecutive rows.
+    pub row_gap: f32,
+    /// Size of the grid, used to size the `Fraction` tracks. If `None`, the maximum size allowed
+    /// by the constraints is used instead, and the grid takes the size of its tracks.
+    pub size: Option<Vec2<f32>>,
+    /// The children.
+    pub children: Vec<GridChild<'a, E>>,
+}
+
+/// Size of a row or a column of a `Grid`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    /// Always the given size.
+    Fixed(f32),
+    /// The size of the largest child in the track.
+    Auto,
+    /// A share of the space left by the other tracks, proportional to the given value.
+    Fraction(f32),
+}
+
+pub struct GridChild<'a, E> {
+    pub child: &'a mut RawComponent<E>,
+    /// Index of the first column of the cell.
+    pub column: usize,
+    /// Index of the first row of the cell.
+    pub row: usize,
+    /// Number of columns that the cell covers. Must be at least 1.
+    pub column_span: usize,
+    /// Number of rows that the cell covers. Must be at least 1.
+    pub row_span: usize,
+    /// How the child is placed horizontally in the cell.
+    pub horizontal_align: Align,
+    /// How the child is placed vertically in the cell.
+    pub vertical_align: Align,
+}
+
+impl<'a, E> GridChild<'a, E> {
+    /// Builds a child that covers a single cell, at the bottom left of it.
+    pub fn new(child: &'a mut RawComponent<E>, column: usize, row: usize) -> GridChild<'a, E> {
+        GridChild {
+            child: child,
+            column: column,
+            row: row,
+            column_span: 1,
+            row_span: 1,
+            horizontal_align: Align::Start,
+            vertical_align: Align::Start,
+        }
+    }
 }
 
 impl<T, E> RawComponent<E> for T where T: Component, E: From<T::EmittedEvent> {
-    fn render(&mut self) -> Vec<Shape> {
-        match self.get_layout() {
-            Layout::SingleChild(child) => {
-                child.render()
-            },
+    fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
+        let clip = self.clips_children();
+
+        let mut result = Vec::new();
+        let children = layout_children(self, &BoxConstraints::tight(size)).0;
+        for (child, transform, child_size) in children {
+            result.extend(child.render(child_size).into_iter().map(|s| {
+                let s = s.transform(&transform);
+                if clip { s.clip(Vec2::new(0.0, 0.0), size) } else { s }
+            }));
+        }
+        result
+    }
 
-            Layout::HorizontalBox(children) => {
-                let mut result = Vec::new();
-                let mut x = 0.0;
+    fn set_pointer_status(&mut self, size: Vec2<f32>, pointer: PointerId,
+                          position: Option<Vec2<f32>>, pressed: bool) -> Vec<E>
+    {
+        let single_child = match self.get_layout() {
+            Layout::SingleChild(_) => true,
+            _ => false,
+        };
 
-                for child in children {
-                    result.extend(child.render().into_iter().map(|s| s.translate(Vec2::new(x, 0.0))));
-                    x += child.get_width();
-                }
+        let position = children_position(self, size, position);
 
-                result
-            },
+        let mut children = layout_children(self, &BoxConstraints::tight(size)).0;
+
+        let events = if single_child {
+            // a single child always receives the position of the pointer
+            let (child, transform, size) = children.remove(0);
+            let position = position.and_then(|p| to_child(&transform, p));
+            child.set_pointer_status(size, pointer, position, pressed)
+                 .into_iter().map(|ev| (0usize, ev)).collect::<Vec<_>>()
+        } else {
+            offset_children_pointer_status(children, pointer, position, pressed)
+        };
+
+        events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect()
+    }
 
-            Layout::VerticalBox(children) => {
-                let mut result = Vec::new();
-                let mut y = 0.0;
+    fn hit_test(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> bool {
+        let shapes = match self.get_bounding_box() {
+            Some(_) => Vec::with_capacity(0),
+            None => RawComponent::<E>::render(self, size),
+        };
+
+        contains(bounding_box(self, &shapes), position)
+    }
 
-                for child in children {
-                    result.extend(child.render().into_iter().map(|s| s.translate(Vec2::new(0.0, y))));
-                    y += child.get_height();
+    fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+        layout_children(self, constraints).1
+    }
+
+    fn get_margin(&mut self) -> Insets {
+        Component::get_margin(self)
+    }
+
+    fn pointer_event(&mut self, size: Vec2<f32>, path: &[usize], pointer: PointerId,
+                     position: Vec2<f32>, event: PointerEvent) -> Vec<E>
+    {
+        let mut events = Vec::with_capacity(0);
+
+        if !path.is_empty() {
+            let mut children = layout_children(self, &BoxConstraints::tight(size)).0;
+            if path[0] < children.len() {
+                let (child, transform, size) = children.swap_remove(path[0]);
+                if let Some(position) = to_child(&transform, position) {
+                    events.extend(child.pointer_event(size, &path[1 ..], pointer, position, event)
+                                       .into_iter().map(|ev| (path[0], ev)));
                 }
+            }
+        }
 
-                result
-            },
+        let mut events = events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect::<Vec<_>>();
 
-            Layout::PositionnedChildren(children) => {
-                let mut result = Vec::new();
-                for child in children {
-                    result.extend(child.child.render().into_iter().map(|s| s.translate(Vec2::new(child.x, child.y))));
+        events.extend(self.handle_pointer_event(pointer, event).into_iter().map(|e| From::from(e)));
+        events
+    }
+
+    fn set_captured_pointer_status(&mut self, size: Vec2<f32>, path: &[usize],
+                                   pointer: PointerId, position: Vec2<f32>, pressed: bool)
+                                   -> Vec<E>
+    {
+        if path.is_empty() {
+            let mut events = self.set_pointer_status(size, pointer, Some(position), pressed);
+            events.extend(self.handle_captured_pointer_move(pointer, position, pressed)
+                              .into_iter().map(|e| From::from(e)));
+            return events;
+        }
+
+        let mut events = Vec::with_capacity(0);
+
+        let children = layout_children(self, &BoxConstraints::tight(size)).0;
+        for (child_id, (child, transform, size)) in children.into_iter().enumerate() {
+            let child_events = if child_id == path[0] {
+                match to_child(&transform, position) {
+                    Some(position) => {
+                        child.set_captured_pointer_status(size, &path[1 ..], pointer, position,
+                                                          pressed)
+                    },
+                    None => child.set_pointer_status(size, pointer, None, pressed),
                 }
-                result
-            },
+            } else {
+                child.set_pointer_status(size, pointer, None, pressed)
+            };
+
+            events.extend(child_events.into_iter().map(|ev| (child_id, ev)));
         }
+
+        events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect()
     }
 
-    fn set_mouse_status(&mut self, position: Option<Vec2<f32>>, pressed: bool) -> Vec<E> {
-        let events = match self.get_layout() {
-            Layout::SingleChild(child) => {
-                child.set_mouse_status(position, pressed).into_iter().map(|ev| (0usize, ev)).collect::<Vec<_>>()
-            },
+    fn get_capture_path(&mut self, path: &[usize], pointer: PointerId, button: MouseButton)
+                        -> Option<Vec<usize>>
+    {
+        if !path.is_empty() {
+            let mut children = children_of(self.get_layout());
+            if path[0] < children.len() {
+                let child = children.swap_remove(path[0]);
+                if let Some(mut capture) = child.get_capture_path(&path[1 ..], pointer, button) {
+                    capture.insert(0, path[0]);
+                    return Some(capture);
+                }
+            }
+        }
 
-            Layout::HorizontalBox(children) => {
-                let mut events = Vec::with_capacity(0);
+        if self.wants_pointer_capture(pointer, button) {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
 
-                if let Some(position) = position {
-                    let mut position = position;
-                    let mut found = false;
-
-                    for (child_id, child) in children.into_iter().enumerate() {
-                        if found {
-                            events.extend(child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                            continue;
-                        }
-
-                        if child.hit_test(position) {
-                            events.extend(child.set_mouse_status(Some(position), pressed).into_iter().map(|ev| (child_id, ev)));
-                            found = true;
-                            continue;
-                        } else {
-                            events.extend(child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                        }
-
-                        position.x -= child.get_width();
-                    }
+    fn get_drag_start(&mut self, path: &[usize], pointer: PointerId)
+                      -> Option<(Vec<usize>, DragStart)>
+    {
+        if !path.is_empty() {
+            let mut children = children_of(self.get_layout());
+            if path[0] < children.len() {
+                let child = children.swap_remove(path[0]);
+                if let Some((mut source, drag)) = child.get_drag_start(&path[1 ..], pointer) {
+                    source.insert(0, path[0]);
+                    return Some((source, drag));
+                }
+            }
+        }
 
-                } else {
-                    for (child_id, child) in children.into_iter().enumerate() {
-                        events.extend(child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                    }
+        self.start_drag(pointer).map(|drag| (Vec::new(), drag))
+    }
+
+    fn get_drop_target(&mut self, path: &[usize], payload: &Any) -> Option<Vec<usize>> {
+        if !path.is_empty() {
+            let mut children = children_of(self.get_layout());
+            if path[0] < children.len() {
+                let child = children.swap_remove(path[0]);
+                if let Some(mut target) = child.get_drop_target(&path[1 ..], payload) {
+                    target.insert(0, path[0]);
+                    return Some(target);
                 }
+            }
+        }
 
-                events
-            },
+        if self.accepts_drop(payload) {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
 
-            Layout::VerticalBox(children) => {
-                let mut events = Vec::with_capacity(0);
+    fn drag_event(&mut self, size: Vec2<f32>, path: &[usize], position: Vec2<f32>,
+                  event: DragEvent) -> Vec<E>
+    {
+        if path.is_empty() {
+            return self.handle_drag_event(position, event).into_iter().map(|e| From::from(e))
+                       .collect();
+        }
 
-                if let Some(position) = position {
-                    let mut position = position;
-                    let mut found = false;
-
-                    for (child_id, child) in children.into_iter().enumerate() {
-                        if found {
-                            events.extend(child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                            continue;
-                        }
-
-                        if child.hit_test(position) {
-                            events.extend(child.set_mouse_status(Some(position), pressed).into_iter().map(|ev| (child_id, ev)));
-                            found = true;
-                            continue;
-                        } else {
-                            events.extend(child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                        }
-
-                        position.y -= child.get_height();
-                    }
+        let mut events = Vec::with_capacity(0);
 
-                } else {
-                    for (child_id, child) in children.into_iter().enumerate() {
-                        events.extend(child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                    }
+        let mut children = layout_children(self, &BoxConstraints::tight(size)).0;
+        if path[0] < children.len() {
+            let (child, transform, size) = children.swap_remove(path[0]);
+            if let Some(position) = to_child(&transform, position) {
+                events.extend(child.drag_event(size, &path[1 ..], position, event)
+                                   .into_iter().map(|ev| (path[0], ev)));
+            }
+        }
+
+        events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect()
+    }
+
+    fn set_hovered_paths(&mut self, old: &[&[usize]], new: &[&[usize]]) -> Vec<E> {
+        let mut events = Vec::with_capacity(0);
+
+        for (child_id, child) in children_of(self.get_layout()).into_iter().enumerate() {
+            let old = old.iter().filter(|p| p.first() == Some(&child_id)).map(|p| &p[1 ..])
+                         .collect::<Vec<_>>();
+            let new = new.iter().filter(|p| p.first() == Some(&child_id)).map(|p| &p[1 ..])
+                         .collect::<Vec<_>>();
+
+            if old != new {
+                events.extend(child.set_hovered_paths(&old, &new).into_iter().map(|ev| (child_id, ev)));
+            }
+        }
+
+        let mut events = events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect::<Vec<_>>();
+
+        let status = HoveredStatus::from_paths(new);
+        if status != HoveredStatus::from_paths(old) {
+            events.extend(self.set_hovered_status(status).into_iter().map(|e| From::from(e)));
+        }
+
+        events
+    }
+
+    fn tick(&mut self, elapsed: f32) -> Vec<E> {
+        let mut events = Vec::with_capacity(0);
+
+        for (child_id, child) in children_of(self.get_layout()).into_iter().enumerate() {
+            events.extend(child.tick(elapsed).into_iter().map(|ev| (child_id, ev)));
+        }
+
+        let mut events = events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect::<Vec<_>>();
+
+        events.extend(Component::tick(self, elapsed).into_iter().map(|e| From::from(e)));
+        events
+    }
+
+    fn is_animating(&mut self) -> bool {
+        if Component::is_animating(self) {
+            return true;
+        }
+
+        children_of(self.get_layout()).into_iter().any(|child| child.is_animating())
+    }
+
+    fn hit_path(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> Option<Vec<usize>> {
+        RawComponent::<E>::render_hit_path(self, size, position).1
+    }
+
+    fn render_hit_path(&mut self, size: Vec2<f32>, position: Vec2<f32>)
+                       -> (Vec<Shape>, Option<Vec<usize>>)
+    {
+        let clip = self.clips_children();
+        let inside = children_position(self, size, Some(position)).is_some();
+
+        let mut shapes = Vec::new();
+        let mut path = None;
+
+        let children = layout_children(self, &BoxConstraints::tight(size)).0;
+        for (child_id, (child, transform, child_size)) in children.into_iter().enumerate() {
+            let child_position = if inside { to_child(&transform, position) } else { None };
+            let (child_shapes, child_path) = match child_position {
+                Some(position) => child.render_hit_path(child_size, position),
+                None => (child.render(child_size), None),
+            };
+
+            // the children that come last are on top of the previous ones
+            if let Some(mut child_path) = child_path {
+                child_path.insert(0, child_id);
+                path = Some(child_path);
+            }
+
+            shapes.extend(child_shapes.into_iter().map(|s| {
+                let s = s.transform(&transform);
+                if clip { s.clip(Vec2::new(0.0, 0.0), size) } else { s }
+            }));
+        }
+
+        if path.is_none() && contains(bounding_box(self, &shapes), position) {
+            path = Some(Vec::new());
+        }
+
+        (shapes, path)
+    }
+
+    fn scroll(&mut self, size: Vec2<f32>, position: Vec2<f32>, delta: ScrollDelta)
+              -> (bool, Vec<E>)
+    {
+        let mut handled = false;
+        let mut events = Vec::with_capacity(0);
+
+        if children_position(self, size, Some(position)).is_some() {
+            let children = layout_children(self, &BoxConstraints::tight(size)).0;
+            for (child_id, (child, transform, size)) in children.into_iter().enumerate().rev() {
+                let position = match to_child(&transform, position) {
+                    Some(position) => position,
+                    None => continue,
+                };
+
+                if child.hit_test(size, position) {
+                    let (child_handled, child_events) = child.scroll(size, position, delta);
+                    handled = child_handled;
+                    events.extend(child_events.into_iter().map(|ev| (child_id, ev)));
+                    break;
                 }
+            }
+        }
 
-                events
-            },
+        let mut events = events.into_iter().filter_map(|(id, ev)| {
+            self.handle_child_event(id, ev)
+        }).map(|e| From::from(e)).collect::<Vec<_>>();
 
-            Layout::PositionnedChildren(children) => {
-                let mut events = Vec::with_capacity(0);
+        if !handled {
+            let (self_handled, event) = self.handle_scroll(delta);
+            handled = self_handled;
+            events.extend(event.into_iter().map(|e| From::from(e)));
+        }
 
+        (handled, events)
+    }
 
-                if let Some(position) = position {
-                    let mut position = position;
-                    let mut found = false;
-
-                    for (child_id, child) in children.into_iter().enumerate() {
-                        let position = {
-                            let mut p = position;
-                            p.x -= child.x;
-                            p.y -= child.y;
-                            p
-                        };
-
-                        if found {
-                            events.extend(child.child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                            continue;
-                        }
-
-                        if child.child.hit_test(position) {
-                            events.extend(child.child.set_mouse_status(Some(position), pressed).into_iter().map(|ev| (child_id, ev)));
-                            found = true;
-                            continue;
-                        } else {
-                            events.extend(child.child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
-                        }
-                    }
+    fn send_character(&mut self, size: Vec2<f32>, position: Option<Vec2<f32>>, character: char)
+                      -> Vec<E>
+    {
+        let events = match children_position(self, size, position) {
+            Some(position) => {
+                let mut events = Vec::with_capacity(0);
 
-                } else {
-                    for (child_id, child) in children.into_iter().enumerate() {
-                        events.extend(child.child.set_mouse_status(None, pressed).into_iter().map(|ev| (child_id, ev)));
+                let children = layout_children(self, &BoxConstraints::tight(size)).0;
+                for (child_id, (child, transform, size)) in children.into_iter().enumerate().rev() {
+                    let position = match to_child(&transform, position) {
+                        Some(position) => position,
+                        None => continue,
+                    };
+
+                    if child.hit_test(size, position) {
+                        events.extend(child.send_character(size, Some(position), character).into_iter().map(|ev| (child_id, ev)));
+                        break;
                     }
                 }
 
                 events
             },
+            None => Vec::with_capacity(0),
         };
 
         events.into_iter().filter_map(|(id, ev)| {
@@ -219,115 +824,1238 @@ impl<T, E> RawComponent<E> for T where T: Component, E: From<T::EmittedEvent> {
         }).map(|e| From::from(e)).collect()
     }
 
-    fn hit_test(&mut self, position: Vec2<f32>) -> bool {
-        match self.get_layout() {
-            Layout::SingleChild(child) => {
-                child.hit_test(position)
-            },
+    fn find_test_id(&mut self, id: &str) -> Option<Vec<usize>> {
+        if self.get_test_id() == Some(id) {
+            return Some(Vec::new());
+        }
 
-            Layout::HorizontalBox(children) => {
-                let mut position = position;
+        for (child_id, child) in children_of(self.get_layout()).into_iter().enumerate() {
+            if let Some(mut path) = child.find_test_id(id) {
+                path.insert(0, child_id);
+                return Some(path);
+            }
+        }
 
-                for child in children {
-                    if child.hit_test(position) {
-                        return true;
-                    }
+        None
+    }
 
-                    position.x -= child.get_width();
-                }
+    fn get_child_area(&mut self, size: Vec2<f32>, path: &[usize])
+                      -> Option<(Vec2<f32>, Vec2<f32>)>
+    {
+        if path.is_empty() {
+            return Some((Vec2::new(0.0, 0.0), size));
+        }
 
-                false
-            },
+        let mut children = layout_children(self, &BoxConstraints::tight(size)).0;
+        if path[0] >= children.len() {
+            return None;
+        }
 
-            Layout::VerticalBox(children) => {
-                let mut position = position;
+        let (child, transform, size) = children.swap_remove(path[0]);
+        child.get_child_area(size, &path[1..]).map(|(bottom_left, top_right)| {
+            let area = Shape::Rectangle { from: bottom_left, to: top_right, color: [0.0; 3] };
+            area.transform(&transform).bounding_box()
+        })
+    }
+}
 
-                for child in children {
-                    if child.hit_test(position) {
-                        return true;
-                    }
+/// Returns the position of a pointer for the children of a component, which is `None` if the
+/// component clips its children and the pointer is outside of it.
+fn children_position<T>(component: &T, size: Vec2<f32>, position: Option<Vec2<f32>>)
+                        -> Option<Vec2<f32>>
+    where T: Component
+{
+    position.and_then(|position| {
+        let inside = position.x >= 0.0 && position.x < size.x &&
+                     position.y >= 0.0 && position.y < size.y;
+
+        if inside || !component.clips_children() {
+            Some(position)
+        } else {
+            None
+        }
+    })
+}
 
-                    position.y -= child.get_height();
-                }
+/// Returns the bounding box of a component, given the shapes that it renders.
+fn bounding_box<T>(component: &T, shapes: &[Shape]) -> Option<(Vec2<f32>, Vec2<f32>)>
+    where T: Component
+{
+    match component.get_bounding_box() {
+        Some(bounding_box) => Some(bounding_box),
+        None => {
+            let padding = component.get_padding();
+            Shape::bounding_box_of(shapes).map(|(bl, tr)| {
+                (bl - padding.get_offset(), tr + Vec2::new(padding.right, padding.top))
+            })
+        },
+    }
+}
 
-                false
-            },
+/// Returns true if a position is within a bounding box.
+fn contains(bounding_box: Option<(Vec2<f32>, Vec2<f32>)>, position: Vec2<f32>) -> bool {
+    match bounding_box {
+        Some((bottom_left, top_right)) => {
+            position.x >= bottom_left.x && position.x < top_right.x &&
+            position.y >= bottom_left.y && position.y < top_right.y
+        },
+        None => false,
+    }
+}
 
-            Layout::PositionnedChildren(children) => {
-                for child in children {
-                    let mut position = position;
-                    position.x -= child.x;
-                    position.y -= child.y;
+/// Lays out the children of a component given its constraints.
+///
+/// Returns the children alongside the transformation from their coordinates to the ones of the
+/// component and their size, and the size of the component.
+fn layout_children<'a, T>(component: &'a mut T, constraints: &BoxConstraints)
+                          -> (Vec<(&'a mut RawComponent<T::ReceivedEvent>, Transform, Vec2<f32>)>,
+                              Vec2<f32>)
+    where T: Component
+{
+    let padding = component.get_padding();
+
+    let constraints = match component.get_dimensions() {
+        Some(dimensions) => BoxConstraints::tight(constraints.constrain(dimensions)),
+        None => *constraints,
+    };
+
+    let inner = constraints.deflate(padding.get_total());
+    let (mut children, content) = children_positions(component.get_layout(), &inner);
+
+    for &mut (_, ref mut transform, _) in children.iter_mut() {
+        *transform = transform.then(&Transform::translation(padding.get_offset()));
+    }
 
-                    if child.child.hit_test(position) {
-                        return true;
-                    }
+    (children, constraints.constrain(content + padding.get_total()))
+}
+
+/// Returns the children of a layout, without computing where they go.
+fn children_of<'a, E>(layout: Layout<'a, E>) -> Vec<&'a mut RawComponent<E>> {
+    match layout {
+        Layout::SingleChild(child) => vec![child],
+        Layout::HorizontalBox(children, _) => children,
+        Layout::VerticalBox(children, _) => children,
+        Layout::PositionnedChildren(children, _) => children.into_iter().map(|c| c.child).collect(),
+        Layout::Flex(flex) => flex.children.into_iter().map(|c| c.child).collect(),
+        Layout::Grid(grid) => grid.children.into_iter().map(|c| c.child).collect(),
+        Layout::Stack(children, _) => children.into_iter().map(|c| c.child).collect(),
+        Layout::Transformed(transformed) => vec![transformed.child],
+    }
+}
+
+/// Lays out the children of a layout given the constraints of the area in which they go.
+///
+/// Returns the children alongside the transformation from their coordinates to the ones of the
+/// parent and their size, and the size of the content of the layout.
+fn children_positions<'a, E>(layout: Layout<'a, E>, constraints: &BoxConstraints)
+                             -> (Vec<(&'a mut RawComponent<E>, Transform, Vec2<f32>)>, Vec2<f32>)
+{
+    let mut transform = Transform::identity();
+
+    let (mut result, content) = match layout {
+        Layout::SingleChild(child) => {
+            let size = measure(child, constraints);
+            (vec![(child, Vec2::new(0.0, 0.0), size)], size)
+        },
+
+        Layout::Transformed(transformed) => {
+            let size = measure(transformed.child, constraints);
+            let pivot = Vec2::new(transformed.pivot.x * size.x, transformed.pivot.y * size.y);
+            transform = Transform::translation(Vec2::new(-pivot.x, -pivot.y))
+                            .then(&transformed.transform)
+                            .then(&Transform::translation(pivot));
+
+            (vec![(transformed.child, Vec2::new(0.0, 0.0), size)], size)
+        },
+
+        Layout::HorizontalBox(children, spacing) => {
+            let constraints = BoxConstraints {
+                min: Vec2::new(0.0, 0.0),
+                max: Vec2::new(f32::INFINITY, constraints.max.y),
+            };
+
+            let mut result = Vec::with_capacity(children.len());
+            let mut x = 0.0;
+            let mut height = 0.0f32;
+
+            for child in children {
+                let size = measure(child, &constraints);
+                result.push((child, Vec2::new(x, 0.0), size));
+                x += size.x + spacing;
+                height = height.max(size.y);
+            }
+
+            let width = if result.is_empty() { 0.0 } else { x - spacing };
+            (result, Vec2::new(width, height))
+        },
+
+        Layout::VerticalBox(children, spacing) => {
+            let constraints = BoxConstraints {
+                min: Vec2::new(0.0, 0.0),
+                max: Vec2::new(constraints.max.x, f32::INFINITY),
+            };
+
+            let mut result = Vec::with_capacity(children.len());
+            let mut y = 0.0;
+            let mut width = 0.0f32;
+
+            for child in children {
+                let size = measure(child, &constraints);
+                result.push((child, Vec2::new(0.0, y), size));
+                y += size.y + spacing;
+                width = width.max(size.x);
+            }
+
+            let height = if result.is_empty() { 0.0 } else { y - spacing };
+            (result, Vec2::new(width, height))
+        },
+
+        Layout::PositionnedChildren(children, size) => {
+            let area = size.unwrap_or(constraints.max);
+            // anchors and relative sizes are meaningless along unbounded axes
+            let finite_area = Vec2::new(if area.x.is_finite() { area.x } else { 0.0 },
+                                        if area.y.is_finite() { area.y } else { 0.0 });
+
+            let mut result = Vec::with_capacity(children.len());
+            let mut min = Vec2::new(0.0f32, 0.0);
+            let mut max = Vec2::new(0.0f32, 0.0);
+            // whether the positions along each axis depend on the area
+            let mut uses_area = (false, false);
+
+            for child in children {
+                uses_area.0 |= child.anchor.x != 0.0 || child.relative_width.is_some();
+                uses_area.1 |= child.anchor.y != 0.0 || child.relative_height.is_some();
+
+                let mut constraints = BoxConstraints::loose(area);
+                if let Some(width) = child.relative_width {
+                    constraints.min.x = width * finite_area.x;
+                    constraints.max.x = width * finite_area.x;
+                }
+                if let Some(height) = child.relative_height {
+                    constraints.min.y = height * finite_area.y;
+                    constraints.max.y = height * finite_area.y;
                 }
 
-                false
-            },
-        }
+                let child_size = measure(child.child, &constraints);
+                let position = Vec2::new(
+                    child.anchor.x * finite_area.x - child.pivot.x * child_size.x + child.x,
+                    child.anchor.y * finite_area.y - child.pivot.y * child_size.y + child.y,
+                );
+
+                min = Vec2::new(min.x.min(position.x), min.y.min(position.y));
+                max = Vec2::new(max.x.max(position.x + child_size.x),
+                                max.y.max(position.y + child_size.y));
+                result.push((child.child, position, child_size));
+            }
+
+            let size = match size {
+                Some(size) => size,
+                None => {
+                    // children at negative positions push the others instead of overflowing,
+                    // unless that would move them away from their anchors
+                    let shift = Vec2::new(if uses_area.0 { 0.0 } else { min.x },
+                                          if uses_area.1 { 0.0 } else { min.y });
+
+                    for &mut (_, ref mut position, _) in result.iter_mut() {
+                        *position = *position - shift;
+                    }
+
+                    max - shift
+                },
+            };
+
+            (result, size)
+        },
+
+        Layout::Flex(flex) => flex_slots(flex, constraints),
+        Layout::Grid(grid) => grid_slots(grid, constraints),
+        Layout::Stack(children, size) => stack_slots(children, size, constraints),
+    };
+
+    // the positions and sizes above are the ones of the margins of the children
+    for &mut (ref mut child, ref mut offset, ref mut size) in result.iter_mut() {
+        let margin = child.get_margin();
+        *offset = *offset + margin.get_offset();
+        *size = *size - margin.get_total();
+        *size = Vec2::new(size.x.max(0.0), size.y.max(0.0));
     }
 
-    fn get_width(&mut self) -> f32 {
-        match self.get_layout() {
-            Layout::SingleChild(child) => {
-                child.get_width()
-            },
+    let result = result.into_iter().map(|(child, offset, size)| {
+        (child, Transform::translation(offset).then(&transform), size)
+    }).collect();
 
-            Layout::HorizontalBox(children) => {
-                children.into_iter().map(|c| c.get_width()).fold(0.0, |a, i| a + i)
-            },
+    (result, content)
+}
 
-            Layout::VerticalBox(children) => {
-                let mut max = 0.0;
-                for child in children {
-                    let width = child.get_width();
-                    if width > max {
-                        max = width;
-                    }
-                }
-                max
-            },
+/// Converts a position relative to a parent into a position relative to a child, given the
+/// transformation of the child. Returns `None` if the transformation can't be inverted, for
+/// example when the child is scaled down to nothing.
+fn to_child(transform: &Transform, position: Vec2<f32>) -> Option<Vec2<f32>> {
+    transform.inverse().map(|inverse| inverse.apply(position))
+}
 
-            Layout::PositionnedChildren(_) => {
-                0.0
-            },
+/// Asks a child which size it wants, given the constraints of the area that includes its
+/// margins. Returns the size including the margins.
+fn measure<E>(child: &mut RawComponent<E>, constraints: &BoxConstraints) -> Vec2<f32> {
+    let margin = child.get_margin().get_total();
+    child.get_size(&constraints.deflate(margin)) + margin
+}
+
+/// Same as `measure`, but reuses the size that the child returned for the looser constraints
+/// `previous` if it satisfies the new ones, as `RawComponent::get_size` requires. Measuring a
+/// child measures its whole subtree, so measuring it twice at each level would make the layout
+/// exponentially slower with nesting.
+fn measure_again<E>(child: &mut RawComponent<E>, previous: &BoxConstraints,
+                    previous_size: Vec2<f32>, constraints: &BoxConstraints) -> Vec2<f32>
+{
+    let within = constraints.min.x >= previous.min.x && constraints.min.y >= previous.min.y &&
+                 constraints.max.x <= previous.max.x && constraints.max.y <= previous.max.y;
+    let fits = previous_size.x >= constraints.min.x && previous_size.y >= constraints.min.y &&
+               previous_size.x <= constraints.max.x && previous_size.y <= constraints.max.y;
+
+    if within && fits {
+        previous_size
+    } else {
+        measure(child, constraints)
+    }
+}
+
+/// Returns the constraints of a child placed with the given alignments in an area.
+fn aligned_constraints(area: Vec2<f32>, horizontal: Align, vertical: Align) -> BoxConstraints {
+    BoxConstraints {
+        min: Vec2::new(if horizontal == Align::Stretch { area.x } else { 0.0 },
+                       if vertical == Align::Stretch { area.y } else { 0.0 }),
+        max: area,
+    }
+}
+
+/// Updates the status of a pointer for children placed with the given transformations. Only the
+/// topmost child under the pointer, which is the last one, receives its position.
+///
+/// Returns the events produced by the children alongside their index.
+fn offset_children_pointer_status<E>(mut children: Vec<(&mut RawComponent<E>, Transform,
+                                                         Vec2<f32>)>,
+                                     pointer: PointerId, position: Option<Vec2<f32>>,
+                                     pressed: bool) -> Vec<(usize, E)>
+{
+    let hit = position.and_then(|position| {
+        children.iter_mut().rposition(|&mut (ref mut child, ref transform, size)| {
+            match to_child(transform, position) {
+                Some(position) => child.hit_test(size, position),
+                None => false,
+            }
+        })
+    });
+
+    let mut events = Vec::with_capacity(0);
+
+    for (child_id, (child, transform, size)) in children.into_iter().enumerate() {
+        let position = if hit == Some(child_id) {
+            position.and_then(|p| to_child(&transform, p))
+        } else {
+            None
+        };
+
+        events.extend(child.set_pointer_status(size, pointer, position, pressed).into_iter()
+                           .map(|ev| (child_id, ev)));
+    }
+
+    events
+}
+
+/// Computes where the children of a `Flex` go.
+///
+/// Returns each child with the position and the size of its margins, and the size of the
+/// layout.
+fn flex_slots<'a, E>(flex: Flex<'a, E>, constraints: &BoxConstraints)
+                     -> (Vec<(&'a mut RawComponent<E>, Vec2<f32>, Vec2<f32>)>, Vec2<f32>)
+{
+    let Flex { direction, justify, align, gap, size, mut children } = flex;
+
+    // converts between (main axis, cross axis) and (x, y)
+    let to_vec = |main: f32, cross: f32| match direction {
+        FlexDirection::Horizontal => Vec2::new(main, cross),
+        FlexDirection::Vertical => Vec2::new(cross, main),
+    };
+    let from_vec = |v: Vec2<f32>| match direction {
+        FlexDirection::Horizontal => (v.x, v.y),
+        FlexDirection::Vertical => (v.y, v.x),
+    };
+
+    let (max_main, max_cross) = from_vec(size.unwrap_or(constraints.max));
+
+    // the children can take as much space as they want along the main axis
+    let natural_constraints = BoxConstraints::loose(to_vec(f32::INFINITY, max_cross));
+    let natural_sizes = children.iter_mut().map(|c| measure(c.child, &natural_constraints))
+                                .collect::<Vec<_>>();
+    let natural = natural_sizes.iter().map(|&s| from_vec(s)).collect::<Vec<_>>();
+
+    let gaps = gap * (children.len().max(1) - 1) as f32;
+    let natural_main = natural.iter().map(|&(m, _)| m).fold(0.0, |a, b| a + b) + gaps;
+    let natural_cross = natural.iter().map(|&(_, c)| c).fold(0.0, |a: f32, b| a.max(b));
+
+    // the layout takes all the space that it is given
+    let (min_main, min_cross) = from_vec(constraints.min);
+    let available_main = if max_main.is_finite() { max_main } else { natural_main.max(min_main) };
+    let available_cross = if max_cross.is_finite() && (align == Align::Stretch || size.is_some()) {
+        max_cross
+    } else {
+        natural_cross.max(min_cross).min(max_cross)
+    };
+
+    // distributing the free space between the children
+    let free = available_main - natural_main;
+    let total_grow = children.iter().map(|c| c.grow).fold(0.0, |a, b| a + b);
+    let total_shrink = children.iter().zip(natural.iter()).map(|(c, &(m, _))| c.shrink * m)
+                               .fold(0.0, |a, b| a + b);
+
+    let mains = children.iter().zip(natural.iter()).map(|(child, &(main, _))| {
+        if free > 0.0 && total_grow > 0.0 {
+            main + free * child.grow / total_grow
+        } else if free < 0.0 && total_shrink > 0.0 {
+            (main + free * child.shrink * main / total_shrink).max(0.0)
+        } else {
+            main
         }
+    }).collect::<Vec<_>>();
+
+    let remaining = available_main - mains.iter().fold(0.0, |a, b| a + b) - gaps;
+    let (mut position, spacing) = match justify {
+        Justify::Start => (0.0, gap),
+        Justify::Center => (remaining / 2.0, gap),
+        Justify::End => (remaining, gap),
+        Justify::SpaceBetween if children.len() >= 2 => {
+            (0.0, gap + remaining.max(0.0) / (children.len() - 1) as f32)
+        },
+        Justify::SpaceBetween => (0.0, gap),
+    };
+
+    let mut result = Vec::with_capacity(children.len());
+
+    for ((child, main), natural_size) in children.into_iter().zip(mains).zip(natural_sizes) {
+        let min_cross = if align == Align::Stretch { available_cross } else { 0.0 };
+        let constraints = BoxConstraints {
+            min: to_vec(main, min_cross),
+            max: to_vec(main, available_cross),
+        };
+
+        let (_, cross) = from_vec(measure_again(child.child, &natural_constraints, natural_size,
+                                                &constraints));
+        let (cross_position, cross) = align.place(cross, available_cross);
+
+        result.push((child.child, to_vec(position, cross_position), to_vec(main, cross)));
+        position += main + spacing;
     }
 
-    fn get_height(&mut self) -> f32 {
-        match self.get_layout() {
-            Layout::SingleChild(child) => {
-                child.get_height()
-            },
+    (result, to_vec(available_main, available_cross))
+}
 
-            Layout::HorizontalBox(children) => {
-                let mut max = 0.0;
-                for child in children {
-                    let width = child.get_height();
-                    if width > max {
-                        max = width;
-                    }
-                }
-                max
-            },
+/// Computes where the children of a `Stack` go.
+///
+/// Returns each child with the position and the size of its margins, and the size of the
+/// layout.
+fn stack_slots<'a, E>(mut children: Vec<StackChild<'a, E>>, size: Option<Vec2<f32>>,
+                      constraints: &BoxConstraints)
+                      -> (Vec<(&'a mut RawComponent<E>, Vec2<f32>, Vec2<f32>)>, Vec2<f32>)
+{
+    let loose = constraints.loosen();
+    let (size, natural) = match size {
+        Some(size) => (size, None),
+        None => {
+            let natural = children.iter_mut().map(|c| measure(c.child, &loose))
+                                  .collect::<Vec<_>>();
+            let largest = natural.iter().fold(Vec2::new(0.0f32, 0.0),
+                                              |a, s| Vec2::new(a.x.max(s.x), a.y.max(s.y)));
+            (constraints.constrain(largest), Some(natural))
+        },
+    };
+
+    let result = children.into_iter().enumerate().map(|(index, child)| {
+        let constraints = aligned_constraints(size, child.horizontal_align,
+                                              child.vertical_align);
+        let child_size = match natural {
+            Some(ref natural) => measure_again(child.child, &loose, natural[index],
+                                               &constraints),
+            None => measure(child.child, &constraints),
+        };
 
-            Layout::VerticalBox(children) => {
-                children.into_iter().map(|c| c.get_height()).fold(0.0, |a, i| a + i)
-            },
+        let (x, width) = child.horizontal_align.place(child_size.x, size.x);
+        let (y, height) = child.vertical_align.place(child_size.y, size.y);
+        (child.child, Vec2::new(x, y), Vec2::new(width, height))
+    }).collect();
 
-            Layout::PositionnedChildren(_) => {
-                0.0
-            },
+    (result, size)
+}
+
+/// Computes where the children of a `Grid` go.
+///
+/// Returns each child with the position and the size of its margins, and the size of the
+/// layout.
+fn grid_slots<'a, E>(grid: Grid<'a, E>, constraints: &BoxConstraints)
+                     -> (Vec<(&'a mut RawComponent<E>, Vec2<f32>, Vec2<f32>)>, Vec2<f32>)
+{
+    let Grid { mut columns, mut rows, column_gap, row_gap, size, mut children } = grid;
+
+    let available = size.unwrap_or(constraints.max);
+
+    let mut sizes = Vec::with_capacity(children.len());
+    for child in children.iter_mut() {
+        child.column_span = child.column_span.max(1);
+        child.row_span = child.row_span.max(1);
+        sizes.push(measure(child.child, &BoxConstraints::unbounded()));
+    }
+
+    // adding the implicit tracks
+    for child in children.iter() {
+        while columns.len() < child.column + child.column_span {
+            columns.push(Track::Auto);
+        }
+        while rows.len() < child.row + child.row_span {
+            rows.push(Track::Auto);
         }
     }
+
+    let widths = {
+        let cells = children.iter().zip(sizes.iter())
+                            .map(|(c, s)| (c.column, c.column_span, s.x)).collect::<Vec<_>>();
+        let available = if available.x.is_finite() { Some(available.x) } else { None };
+        track_sizes(&columns, column_gap, available, &cells)
+    };
+
+    let heights = {
+        let cells = children.iter().zip(sizes.iter())
+                            .map(|(c, s)| (c.row, c.row_span, s.y)).collect::<Vec<_>>();
+        let available = if available.y.is_finite() { Some(available.y) } else { None };
+        track_sizes(&rows, row_gap, available, &cells)
+    };
+
+    let xs = track_positions(&widths, column_gap);
+    let ys = track_positions(&heights, row_gap);
+
+    let mut result = Vec::with_capacity(children.len());
+
+    for (child, natural) in children.into_iter().zip(sizes) {
+        let cell_width = xs[child.column + child.column_span - 1] +
+                         widths[child.column + child.column_span - 1] - xs[child.column];
+        let cell_height = ys[child.row + child.row_span - 1] +
+                          heights[child.row + child.row_span - 1] - ys[child.row];
+
+        let cell = Vec2::new(cell_width, cell_height);
+        let constraints = aligned_constraints(cell, child.horizontal_align,
+                                              child.vertical_align);
+        let size = measure_again(child.child, &BoxConstraints::unbounded(), natural,
+                                 &constraints);
+
+        let (x, width) = child.horizontal_align.place(size.x, cell.x);
+        let (y, height) = child.vertical_align.place(size.y, cell.y);
+
+        result.push((child.child, Vec2::new(xs[child.column] + x, ys[child.row] + y),
+                     Vec2::new(width, height)));
+    }
+
+    let total = match size {
+        Some(size) => size,
+        None => {
+            let total = |sizes: &[f32], gap: f32| {
+                sizes.iter().fold(0.0, |a, b| a + b) + gap * (sizes.len().max(1) - 1) as f32
+            };
+            Vec2::new(total(&widths, column_gap), total(&heights, row_gap))
+        },
+    };
+
+    (result, total)
 }
 
 /// State of a component in regards to the mouse position.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HoveredStatus {
+    /// The component is the deepest component under a pointer.
     Hovered,
+    /// One of the descendants of the component is under a pointer.
     ChildHovered,
+    /// No pointer is over the component.
     NotHovered,
 }
+
+impl HoveredStatus {
+    /// Returns the status of a component given the paths of the deepest components under each
+    /// pointer, relative to it.
+    fn from_paths(paths: &[&[usize]]) -> HoveredStatus {
+        if paths.iter().any(|p| p.is_empty()) {
+            HoveredStatus::Hovered
+        } else if !paths.is_empty() {
+            HoveredStatus::ChildHovered
+        } else {
+            HoveredStatus::NotHovered
+        }
+    }
+}
+
+/// Computes the sizes of the rows or of the columns of a grid.
+///
+/// `cells` contains the index of the first track, the number of tracks and the size of each
+/// child.
+fn track_sizes(tracks: &[Track], gap: f32, available: Option<f32>,
+               cells: &[(usize, usize, f32)]) -> Vec<f32>
+{
+    let mut sizes = tracks.iter().map(|t| match t {
+        &Track::Fixed(size) => size,
+        _ => 0.0,
+    }).collect::<Vec<_>>();
+
+    // tracks that depend on their content are first sized from the children that only cover
+    // them
+    for &(start, span, size) in cells.iter() {
+        if span == 1 {
+            if let Track::Fixed(_) = tracks[start] {
+                continue;
+            }
+
+            let size = match tracks[start] {
+                Track::Fraction(fraction) if available.is_none() && fraction > 0.0 => {
+                    size / fraction
+                },
+                _ => size,
+            };
+
+            if size > sizes[start] {
+                sizes[start] = size;
+            }
+        }
+    }
+
+    let total_fraction = tracks.iter().map(|t| match t {
+        &Track::Fraction(fraction) => fraction,
+        _ => 0.0,
+    }).fold(0.0, |a, b| a + b);
+
+    let fraction_unit = match available {
+        Some(available) => {
+            let others = tracks.iter().zip(sizes.iter()).map(|(t, &s)| match t {
+                &Track::Fraction(_) => 0.0,
+                _ => s,
+            }).fold(0.0, |a, b| a + b);
+            let gaps = gap * (tracks.len().max(1) - 1) as f32;
+
+            if total_fraction > 0.0 {
+                (available - others - gaps).max(0.0) / total_fraction
+            } else {
+                0.0
+            }
+        },
+        None => {
+            // without an available size, the unit is the smallest one in which every child of
+            // a fractional track fits
+            tracks.iter().zip(sizes.iter()).filter_map(|(t, &s)| match t {
+                &Track::Fraction(_) => Some(s),
+                _ => None,
+            }).fold(0.0, |a: f32, b| a.max(b))
+        },
+    };
+
+    for (track, size) in tracks.iter().zip(sizes.iter_mut()) {
+        if let &Track::Fraction(fraction) = track {
+            *size = fraction * fraction_unit;
+        }
+    }
+
+    // children covering multiple tracks enlarge the `Auto` tracks they cover if they don't fit
+    for &(start, span, size) in cells.iter() {
+        if span == 1 {
+            continue;
+        }
+
+        let covered = sizes[start .. start + span].iter().fold(0.0, |a, b| a + b) +
+                      gap * (span - 1) as f32;
+        let autos = tracks[start .. start + span].iter().filter(|t| **t == Track::Auto).count();
+
+        if size > covered && autos >= 1 {
+            let extra = (size - covered) / autos as f32;
+            let covered_tracks = tracks[start .. start + span].iter();
+            for (track, track_size) in covered_tracks.zip(sizes[start ..].iter_mut()) {
+                if *track == Track::Auto {
+                    *track_size += extra;
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Returns the position of the start of each track, given their sizes.
+fn track_positions(sizes: &[f32], gap: f32) -> Vec<f32> {
+    let mut result = Vec::with_capacity(sizes.len());
+    let mut position = 0.0;
+
+    for size in sizes.iter() {
+        result.push(position);
+        position += size + gap;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vec2;
+
+    use component::{BoxConstraints, Insets, PointerId, RawComponent};
+    use predefined::ButtonComponent;
+    use predefined::button::ButtonEvent;
+    use shape::Shape;
+    use super::{Align, Anchor, Component, Flex, FlexChild, FlexDirection, Grid, GridChild};
+    use super::{Justify, Layout, PositionnedChild, StackChild, Track};
+
+    /// Two buttons of 100 by 30, one of them at a negative position.
+    #[derive(Default)]
+    struct Positionned {
+        left: ButtonComponent,
+        right: ButtonComponent,
+        size: Option<Vec2<f32>>,
+    }
+
+    impl Component for Positionned {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::PositionnedChildren(vec![
+                PositionnedChild::new(&mut self.left, -50.0, 0.0),
+                PositionnedChild::new(&mut self.right, 100.0, 20.0),
+            ], self.size)
+        }
+    }
+
+    #[derive(Default)]
+    struct Column {
+        positionned: Positionned,
+        below: ButtonComponent,
+    }
+
+    impl Component for Column {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::VerticalBox(vec![&mut self.below, &mut self.positionned], 0.0)
+        }
+    }
+
+    fn area<T>(component: &mut T, path: &[usize]) -> (Vec2<f32>, Vec2<f32>)
+        where T: RawComponent<ButtonEvent>
+    {
+        let size = component.get_size(&BoxConstraints::loose(Vec2::new(800.0, 600.0)));
+        component.get_child_area(size, path).unwrap()
+    }
+
+    #[test]
+    fn positionned_size_is_extent_of_children() {
+        let mut positionned: Positionned = Default::default();
+        let constraints = BoxConstraints::unbounded();
+        let size = RawComponent::<ButtonEvent>::get_size(&mut positionned, &constraints);
+        assert_eq!(size, Vec2::new(250.0, 50.0));
+    }
+
+    #[test]
+    fn negative_positions_dont_overflow() {
+        let mut column: Column = Default::default();
+
+        assert_eq!(area(&mut column, &[0]), (Vec2::new(0.0, 0.0), Vec2::new(100.0, 30.0)));
+        assert_eq!(area(&mut column, &[1]), (Vec2::new(0.0, 30.0), Vec2::new(250.0, 80.0)));
+        assert_eq!(area(&mut column, &[1, 0]), (Vec2::new(0.0, 30.0), Vec2::new(100.0, 60.0)));
+        assert_eq!(area(&mut column, &[1, 1]), (Vec2::new(150.0, 50.0), Vec2::new(250.0, 80.0)));
+    }
+
+    /// A button anchored to the top-right hand corner, and one at a negative position.
+    #[derive(Default)]
+    struct Anchored {
+        corner: ButtonComponent,
+        left: ButtonComponent,
+    }
+
+    impl Component for Anchored {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::PositionnedChildren(vec![
+                PositionnedChild::anchored(&mut self.corner, Anchor::TopRight, -10.0, 0.0),
+                PositionnedChild::new(&mut self.left, -50.0, -5.0),
+            ], None)
+        }
+    }
+
+    #[test]
+    fn anchored_children_stay_at_their_anchor() {
+        let mut anchored: Anchored = Default::default();
+        let constraints = BoxConstraints::tight(Vec2::new(300.0, 100.0));
+        let size = RawComponent::<ButtonEvent>::get_size(&mut anchored, &constraints);
+        assert_eq!(size, Vec2::new(300.0, 100.0));
+
+        // moving the children would move the anchored one away from its corner
+        let corner = RawComponent::<ButtonEvent>::get_child_area(&mut anchored, size, &[0]);
+        assert_eq!(corner, Some((Vec2::new(190.0, 70.0), Vec2::new(290.0, 100.0))));
+        let left = RawComponent::<ButtonEvent>::get_child_area(&mut anchored, size, &[1]);
+        assert_eq!(left, Some((Vec2::new(-50.0, -5.0), Vec2::new(50.0, 25.0))));
+    }
+
+    #[test]
+    fn explicit_size_keeps_positions() {
+        let mut positionned = Positionned { size: Some(Vec2::new(300.0, 100.0)),
+                                            .. Default::default() };
+
+        assert_eq!(area(&mut positionned, &[]), (Vec2::new(0.0, 0.0), Vec2::new(300.0, 100.0)));
+        assert_eq!(area(&mut positionned, &[0]), (Vec2::new(-50.0, 0.0), Vec2::new(50.0, 30.0)));
+    }
+
+    /// A square of 10 by 10 that counts how many times it is measured and rendered.
+    #[derive(Default)]
+    struct Counted {
+        measures: usize,
+        renders: usize,
+    }
+
+    impl RawComponent<ButtonEvent> for Counted {
+        fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
+            self.renders += 1;
+            vec![Shape::Rectangle { from: Vec2::new(0.0, 0.0), to: size, color: [0.0; 3] }]
+        }
+
+        fn set_pointer_status(&mut self, _: Vec2<f32>, _: PointerId, _: Option<Vec2<f32>>,
+                              _: bool) -> Vec<ButtonEvent>
+        {
+            Vec::with_capacity(0)
+        }
+
+        fn hit_test(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> bool {
+            position.x >= 0.0 && position.x < size.x && position.y >= 0.0 && position.y < size.y
+        }
+
+        fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+            self.measures += 1;
+            constraints.constrain(Vec2::new(10.0, 10.0))
+        }
+    }
+
+    /// Alternates flexes, stacks and grids around a `Counted`.
+    struct Nested {
+        depth: usize,
+        inner: Option<Box<Nested>>,
+        leaf: Counted,
+    }
+
+    impl Nested {
+        fn new(depth: usize) -> Nested {
+            Nested {
+                depth: depth,
+                inner: if depth == 0 { None } else { Some(Box::new(Nested::new(depth - 1))) },
+                leaf: Default::default(),
+            }
+        }
+
+        fn leaf(&self) -> &Counted {
+            match self.inner {
+                Some(ref inner) => inner.leaf(),
+                None => &self.leaf,
+            }
+        }
+    }
+
+    impl Component for Nested {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            let depth = self.depth;
+            let child: &mut RawComponent<ButtonEvent> = match self.inner {
+                Some(ref mut inner) => &mut **inner,
+                None => &mut self.leaf,
+            };
+
+            match depth % 3 {
+                0 => Layout::Flex(Flex {
+                    direction: FlexDirection::Horizontal,
+                    justify: Justify::Start,
+                    align: Align::Start,
+                    gap: 0.0,
+                    size: None,
+                    children: vec![FlexChild::new(child)],
+                }),
+                1 => Layout::Stack(vec![StackChild::new(child, Align::End, Align::Start)], None),
+                _ => Layout::Grid(Grid {
+                    columns: Vec::new(),
+                    rows: Vec::new(),
+                    column_gap: 0.0,
+                    row_gap: 0.0,
+                    size: None,
+                    children: vec![GridChild::new(child, 0, 0)],
+                }),
+            }
+        }
+    }
+
+    #[test]
+    fn children_are_measured_once() {
+        let mut nested = Nested::new(12);
+        let constraints = BoxConstraints::loose(Vec2::new(800.0, 600.0));
+        RawComponent::<ButtonEvent>::get_size(&mut nested, &constraints);
+        assert_eq!(nested.leaf().measures, 1);
+    }
+
+    #[test]
+    fn hit_path_renders_once() {
+        let mut nested = Nested::new(5);
+        let size = Vec2::new(800.0, 600.0);
+
+        // the grids only take the size of the leaf, at the bottom left hand corner
+        assert_eq!(RawComponent::<ButtonEvent>::hit_path(&mut nested, size, Vec2::new(5.0, 5.0))
+                       .map(|p| p.len()), Some(6));
+        assert_eq!(nested.leaf().renders, 1);
+
+        assert!(RawComponent::<ButtonEvent>::hit_path(&mut nested, size,
+                                                       Vec2::new(50.0, 50.0)).is_none());
+        assert_eq!(nested.leaf().renders, 2);
+    }
+
+    /// A child of 100 by 100 that draws nothing.
+    struct Blank;
+
+    impl Component for Blank {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::PositionnedChildren(Vec::new(), None)
+        }
+
+        fn get_dimensions(&self) -> Option<Vec2<f32>> {
+            Some(Vec2::new(100.0, 100.0))
+        }
+    }
+
+    #[test]
+    fn default_bounding_box_comes_from_the_shapes() {
+        let mut blank = Blank;
+        let size = Vec2::new(100.0, 100.0);
+        let position = Vec2::new(50.0, 50.0);
+        assert!(!RawComponent::<ButtonEvent>::hit_test(&mut blank, size, position));
+        assert!(RawComponent::<ButtonEvent>::hit_path(&mut blank, size, position).is_none());
+
+        let mut nested = Nested::new(1);
+        assert!(RawComponent::<ButtonEvent>::hit_test(&mut nested, size, Vec2::new(5.0, 5.0)));
+        assert!(!RawComponent::<ButtonEvent>::hit_test(&mut nested, size, position));
+    }
+
+    /// A rectangle that wants a given size, and the given margin around it.
+    struct Fixed {
+        size: Vec2<f32>,
+        margin: Insets,
+    }
+
+    impl Fixed {
+        fn new(width: f32, height: f32) -> Fixed {
+            Fixed { size: Vec2::new(width, height), margin: Default::default() }
+        }
+    }
+
+    impl RawComponent<ButtonEvent> for Fixed {
+        fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
+            vec![Shape::Rectangle { from: Vec2::new(0.0, 0.0), to: size, color: [0.0; 3] }]
+        }
+
+        fn set_pointer_status(&mut self, _: Vec2<f32>, _: PointerId, _: Option<Vec2<f32>>,
+                              _: bool) -> Vec<ButtonEvent>
+        {
+            Vec::with_capacity(0)
+        }
+
+        fn hit_test(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> bool {
+            position.x >= 0.0 && position.x < size.x && position.y >= 0.0 && position.y < size.y
+        }
+
+        fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+            constraints.constrain(self.size)
+        }
+
+        fn get_margin(&mut self) -> Insets {
+            self.margin
+        }
+    }
+
+    /// Returns the areas of the children of a component given its constraints.
+    fn child_areas<T>(component: &mut T, constraints: &BoxConstraints, count: usize)
+                      -> Vec<(Vec2<f32>, Vec2<f32>)>
+        where T: RawComponent<ButtonEvent>
+    {
+        let size = component.get_size(constraints);
+        (0 .. count).map(|i| component.get_child_area(size, &[i]).unwrap()).collect()
+    }
+
+    /// Two children of 50 by 20 in a horizontal flex, the second one growing twice as much as
+    /// the first one.
+    struct Growing {
+        first: Fixed,
+        second: Fixed,
+        grow: bool,
+        justify: Justify,
+        align: Align,
+    }
+
+    impl Growing {
+        fn new(grow: bool, justify: Justify, align: Align) -> Growing {
+            Growing {
+                first: Fixed::new(50.0, 20.0),
+                second: Fixed::new(50.0, 20.0),
+                grow: grow,
+                justify: justify,
+                align: align,
+            }
+        }
+    }
+
+    impl Component for Growing {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            let grow = if self.grow { 1.0 } else { 0.0 };
+
+            Layout::Flex(Flex {
+                direction: FlexDirection::Horizontal,
+                justify: self.justify,
+                align: self.align,
+                gap: 0.0,
+                size: None,
+                children: vec![
+                    FlexChild { child: &mut self.first, grow: grow, shrink: 1.0 },
+                    FlexChild { child: &mut self.second, grow: 2.0 * grow, shrink: 1.0 },
+                ],
+            })
+        }
+    }
+
+    #[test]
+    fn flex_grow() {
+        let mut growing = Growing::new(true, Justify::Start, Align::Start);
+        let constraints = BoxConstraints::tight(Vec2::new(300.0, 100.0));
+        let areas = child_areas(&mut growing, &constraints, 2);
+
+        // the 200 free units are shared one third and two thirds
+        assert!((areas[0].1.x - 116.66667).abs() < 1e-3);
+        assert_eq!(areas[0].0, Vec2::new(0.0, 0.0));
+        assert_eq!(areas[1].0.x, areas[0].1.x);
+        assert_eq!(areas[1].1, Vec2::new(300.0, 20.0));
+    }
+
+    #[test]
+    fn flex_shrink_and_stretch() {
+        let mut growing = Growing::new(false, Justify::Start, Align::Stretch);
+        let constraints = BoxConstraints::tight(Vec2::new(60.0, 100.0));
+        let areas = child_areas(&mut growing, &constraints, 2);
+
+        // the missing 40 units are taken equally from both children
+        assert_eq!(areas, vec![(Vec2::new(0.0, 0.0), Vec2::new(30.0, 100.0)),
+                               (Vec2::new(30.0, 0.0), Vec2::new(60.0, 100.0))]);
+    }
+
+    #[test]
+    fn flex_space_between() {
+        let mut growing = Growing::new(false, Justify::SpaceBetween, Align::Center);
+        let constraints = BoxConstraints::tight(Vec2::new(300.0, 100.0));
+        let areas = child_areas(&mut growing, &constraints, 2);
+
+        assert_eq!(areas, vec![(Vec2::new(0.0, 40.0), Vec2::new(50.0, 60.0)),
+                               (Vec2::new(250.0, 40.0), Vec2::new(300.0, 60.0))]);
+    }
+
+    /// A column with a padding of 10 and a spacing of 5, whose bottom child has a margin of 2.
+    struct Padded {
+        bottom: Fixed,
+        top: Fixed,
+    }
+
+    impl Component for Padded {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::VerticalBox(vec![&mut self.bottom, &mut self.top], 5.0)
+        }
+
+        fn get_padding(&self) -> Insets {
+            Insets::uniform(10.0)
+        }
+    }
+
+    #[test]
+    fn padding_margins_and_spacing() {
+        let mut padded = Padded {
+            bottom: Fixed { size: Vec2::new(20.0, 20.0), margin: Insets::uniform(2.0) },
+            top: Fixed::new(10.0, 10.0),
+        };
+
+        let constraints = BoxConstraints::loose(Vec2::new(800.0, 600.0));
+        let size = RawComponent::<ButtonEvent>::get_size(&mut padded, &constraints);
+        assert_eq!(size, Vec2::new(44.0, 59.0));
+
+        let areas = child_areas(&mut padded, &constraints, 2);
+        assert_eq!(areas, vec![(Vec2::new(12.0, 12.0), Vec2::new(32.0, 32.0)),
+                               (Vec2::new(10.0, 39.0), Vec2::new(20.0, 49.0))]);
+
+        // the padding belongs to the component, but the margin doesn't
+        let hit = |padded: &mut Padded, x, y| {
+            RawComponent::<ButtonEvent>::hit_path(padded, size, Vec2::new(x, y))
+        };
+        assert_eq!(hit(&mut padded, 15.0, 15.0), Some(vec![0]));
+        assert_eq!(hit(&mut padded, 11.0, 11.0), Some(vec![]));
+        assert_eq!(hit(&mut padded, 5.0, 55.0), Some(vec![]));
+        assert_eq!(hit(&mut padded, 50.0, 5.0), None);
+    }
+
+    /// A grid with columns of 100 and 50, a child spanning both columns and a child in the
+    /// second column of the second row.
+    struct Spanning {
+        wide: Fixed,
+        small: Fixed,
+    }
+
+    impl Component for Spanning {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::Grid(Grid {
+                columns: vec![Track::Fixed(100.0), Track::Fixed(50.0)],
+                rows: vec![Track::Auto, Track::Fixed(30.0)],
+                column_gap: 0.0,
+                row_gap: 0.0,
+                size: None,
+                children: vec![
+                    GridChild {
+                        column_span: 2,
+                        horizontal_align: Align::Stretch,
+                        .. GridChild::new(&mut self.wide, 0, 0)
+                    },
+                    GridChild::new(&mut self.small, 1, 1),
+                ],
+            })
+        }
+    }
+
+    #[test]
+    fn grid_span() {
+        let mut spanning = Spanning { wide: Fixed::new(10.0, 20.0), small: Fixed::new(10.0, 10.0) };
+        let constraints = BoxConstraints::loose(Vec2::new(800.0, 600.0));
+        let size = RawComponent::<ButtonEvent>::get_size(&mut spanning, &constraints);
+        assert_eq!(size, Vec2::new(150.0, 50.0));
+
+        // the spanning child stretches over both columns, and the rows start from the bottom
+        let areas = child_areas(&mut spanning, &constraints, 2);
+        assert_eq!(areas, vec![(Vec2::new(0.0, 0.0), Vec2::new(150.0, 20.0)),
+                               (Vec2::new(100.0, 20.0), Vec2::new(110.0, 30.0))]);
+
+        let hit = RawComponent::<ButtonEvent>::hit_path(&mut spanning, size,
+                                                        Vec2::new(120.0, 10.0));
+        assert_eq!(hit, Some(vec![0]));
+    }
+
+    /// A background filling a stack of 100 by 100, and a badge in its top-right hand corner.
+    struct Overlay {
+        background: Fixed,
+        badge: Fixed,
+    }
+
+    impl Component for Overlay {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::Stack(vec![
+                StackChild::fill(&mut self.background),
+                StackChild::new(&mut self.badge, Align::End, Align::End),
+            ], Some(Vec2::new(100.0, 100.0)))
+        }
+    }
+
+    #[test]
+    fn stack_order() {
+        let mut overlay = Overlay {
+            background: Fixed::new(0.0, 0.0),
+            badge: Fixed::new(20.0, 20.0),
+        };
+        let constraints = BoxConstraints::loose(Vec2::new(800.0, 600.0));
+        let areas = child_areas(&mut overlay, &constraints, 2);
+        assert_eq!(areas, vec![(Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0)),
+                               (Vec2::new(80.0, 80.0), Vec2::new(100.0, 100.0))]);
+
+        // the last child is on top of the first one
+        let size = Vec2::new(100.0, 100.0);
+        let mut hit = |x, y| RawComponent::<ButtonEvent>::hit_path(&mut overlay, size,
+                                                                   Vec2::new(x, y));
+        assert_eq!(hit(90.0, 90.0), Some(vec![1]));
+        assert_eq!(hit(10.0, 90.0), Some(vec![0]));
+        assert_eq!(hit(110.0, 90.0), None);
+    }
+
+    /// A child whose height depends on its width, like a text that wraps. Prefers a width of
+    /// 200 and an area of 2000.
+    struct Wrapping;
+
+    impl RawComponent<ButtonEvent> for Wrapping {
+        fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
+            vec![Shape::Rectangle { from: Vec2::new(0.0, 0.0), to: size, color: [0.0; 3] }]
+        }
+
+        fn set_pointer_status(&mut self, _: Vec2<f32>, _: PointerId, _: Option<Vec2<f32>>,
+                              _: bool) -> Vec<ButtonEvent>
+        {
+            Vec::with_capacity(0)
+        }
+
+        fn hit_test(&mut self, _: Vec2<f32>, _: Vec2<f32>) -> bool {
+            false
+        }
+
+        fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+            let width = 200.0f32.max(constraints.min.x).min(constraints.max.x);
+            constraints.constrain(Vec2::new(width, 2000.0 / width))
+        }
+    }
+
+    /// A `Wrapping` and a `Fixed` of 100 by 10 in a horizontal flex.
+    struct WrappingRow {
+        wrapping: Wrapping,
+        fixed: Fixed,
+    }
+
+    impl Component for WrappingRow {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::Flex(Flex {
+                direction: FlexDirection::Horizontal,
+                justify: Justify::Start,
+                align: Align::Start,
+                gap: 0.0,
+                size: None,
+                children: vec![FlexChild::new(&mut self.wrapping), FlexChild::new(&mut self.fixed)],
+            })
+        }
+    }
+
+    #[test]
+    fn shrunk_children_are_measured_again() {
+        let mut row = WrappingRow { wrapping: Wrapping, fixed: Fixed::new(100.0, 10.0) };
+        let constraints = BoxConstraints::tight(Vec2::new(200.0, 100.0));
+        let areas = child_areas(&mut row, &constraints, 2);
+
+        // the 100 missing units are taken in proportion to the natural widths, and the
+        // narrower child gets taller instead of keeping its natural height
+        let (from, to) = areas[0];
+        assert_eq!(from, Vec2::new(0.0, 0.0));
+        assert!((to.x - 133.33333).abs() < 1e-3);
+        assert!((to.y - 15.0).abs() < 1e-3);
+    }
+}
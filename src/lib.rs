@@ -1,17 +1,31 @@
 #![warn(missing_docs)]
 
 extern crate nalgebra;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 use std::default::Default;
 
 pub use nalgebra::Vec2;
-pub use shape::Shape;
-pub use easy_component::{Component, Layout, PositionnedChild};
-pub use ui::{Ui, UiMainComponentMutRef};
+pub use shape::{Font, FontMetrics, Image, MonospaceMetrics, Shape};
+pub use transform::Transform;
+pub use easy_component::{Align, Anchor, Component, Flex, FlexChild, FlexDirection};
+pub use easy_component::{HoveredStatus, Justify, TransformedChild};
+pub use easy_component::{Grid, GridChild, Layout, PositionnedChild, StackChild, Track};
+pub use ui::{ClickSettings, Origin, Ui, UiMainComponentMutRef, Unit};
 
+pub mod animation;
 pub mod component;
+pub mod drag;
 pub mod predefined;
+pub mod recording;
+#[cfg(feature = "serde")]
+pub mod serde_vec2;
+pub mod snapshot;
+pub mod testing;
 
 mod easy_component;
 mod shape;
+mod transform;
 mod ui;
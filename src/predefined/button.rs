@@ -1,20 +1,30 @@
 use std::any::Any;
+use std::collections::HashMap;
 use std::default::Default;
 use nalgebra::Vec2;
 
 use predefined::TextComponent;
 use shape::{Shape, Font};
-use component::RawComponent;
+use component::{BoxConstraints, PointerId, RawComponent};
+use testing::TestId;
 
 pub struct ButtonComponent {
     color: [f32; 3],
+    size: Vec2<f32>,
     label: TextComponent,
+    pointers: HashMap<PointerId, PointerState>,
+    test_id: TestId,
+}
+
+/// State of the button in regards to one pointer.
+#[derive(Default)]
+struct PointerState {
     hovered: bool,
     can_be_pressed: bool,
     previous_pressed_status: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ButtonEvent {
     Triggered,
 }
@@ -23,26 +33,37 @@ impl ButtonComponent {
     pub fn set_color(&mut self, color: [f32; 3]) {
         self.color = color;
     }
+
+    /// Sets the size that the button wants, in the unit of the `Ui`. The default is 100 by 30
+    /// logical pixels.
+    pub fn set_size(&mut self, size: Vec2<f32>) {
+        self.size = size;
+    }
+
+    /// Sets the identifier that tests can use to locate this component.
+    pub fn set_test_id(&mut self, id: String) {
+        self.test_id.set(id);
+    }
 }
 
 impl Default for ButtonComponent {
     fn default() -> ButtonComponent {
         ButtonComponent {
             color: [1.0, 1.0, 0.0],
-            label: TextComponent::new("Button".to_string(), Font::Button, 0.1),
-            hovered: false,
-            can_be_pressed: false,
-            previous_pressed_status: false,
+            size: Vec2::new(100.0, 30.0),
+            label: TextComponent::new("Button".to_string(), Font::Button, 16.0),
+            pointers: HashMap::new(),
+            test_id: Default::default(),
         }
     }
 }
 
 impl<E> RawComponent<E> for ButtonComponent where E: From<ButtonEvent> {
-    fn render(&mut self) -> Vec<Shape> {
+    fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
         vec![Shape::Rectangle {
             from: Vec2::new(0.0, 0.0),
-            to: Vec2::new(0.1, 0.1),
-            color: if self.hovered {
+            to: size,
+            color: if self.pointers.values().any(|p| p.hovered) {
                 [self.color[0] * 0.8, self.color[1] * 0.8, self.color[2] * 0.8]
             } else {
                 self.color
@@ -50,43 +71,49 @@ impl<E> RawComponent<E> for ButtonComponent where E: From<ButtonEvent> {
         }]
     }
 
-    fn set_mouse_status(&mut self, position: Option<Vec2<f32>>, pressed: bool) -> Vec<E> {
-        let mut ret = if position.is_some() {
-            self.hovered = true;
-            Vec::with_capacity(0)
+    fn set_pointer_status(&mut self, _: Vec2<f32>, pointer: PointerId,
+                          position: Option<Vec2<f32>>, pressed: bool) -> Vec<E>
+    {
+        let mut ret = Vec::with_capacity(0);
 
-        } else {
-            self.hovered = false;
-            Vec::with_capacity(0)
-        };
-
-        if position.is_some() && self.can_be_pressed &&
-            self.previous_pressed_status == true && pressed == false
         {
-            ret.push(From::from(ButtonEvent::Triggered));
-        }
+            let state = self.pointers.entry(pointer).or_insert_with(Default::default);
+            state.hovered = position.is_some();
+
+            if position.is_some() && state.can_be_pressed &&
+                state.previous_pressed_status == true && pressed == false
+            {
+                ret.push(From::from(ButtonEvent::Triggered));
+            }
+
+            if position.is_none() {
+                state.can_be_pressed = false;
+            }
+
+            if position.is_some() && pressed == false {
+                state.can_be_pressed = true;
+            }
 
-        if position.is_none() {
-            self.can_be_pressed = false;
+            state.previous_pressed_status = pressed;
         }
 
-        if position.is_some() && pressed == false {
-            self.can_be_pressed = true;
+        // forgetting about pointers that left, as fingers get a new id for each touch
+        if position.is_none() && !pressed {
+            self.pointers.remove(&pointer);
         }
 
-        self.previous_pressed_status = pressed;
         ret
     }
 
-    fn hit_test(&mut self, pos: Vec2<f32>) -> bool {
-        pos.x >= 0.0 && pos.x < 0.1 && pos.y >= 0.0 && pos.y < 0.1
+    fn hit_test(&mut self, size: Vec2<f32>, pos: Vec2<f32>) -> bool {
+        pos.x >= 0.0 && pos.x < size.x && pos.y >= 0.0 && pos.y < size.y
     }
 
-    fn get_width(&mut self) -> f32 {
-        0.1     // TODO:
+    fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+        constraints.constrain(self.size)
     }
 
-    fn get_height(&mut self) -> f32 {
-        0.1     // TODO:
+    fn find_test_id(&mut self, id: &str) -> Option<Vec<usize>> {
+        self.test_id.find(id)
     }
 }
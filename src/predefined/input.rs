@@ -3,21 +3,26 @@ use std::any::Any;
 use std::default::Default;
 use shape::{Font, Shape};
 
-use component::RawComponent;
+use component::{BoxConstraints, PointerId, RawComponent};
+use testing::TestId;
 
 #[derive(Default)]
 pub struct InputComponent {
     text: String,
     font: Font,
     em: f32,
+    test_id: TestId,
 }
 
 impl InputComponent {
+    /// Builds the component. `em` is the height of the text in the unit of the `Ui`, usually
+    /// logical pixels.
     pub fn new(text: String, font: Font, em: f32) -> InputComponent {
         InputComponent {
             text: text,
             font: font,
             em: em,
+            test_id: Default::default(),
         }
     }
 
@@ -36,10 +41,15 @@ impl InputComponent {
     pub fn set_em(&mut self, em: f32) {
         self.em = em;
     }
+
+    /// Sets the identifier that tests can use to locate this component.
+    pub fn set_test_id(&mut self, id: String) {
+        self.test_id.set(id);
+    }
 }
 
 impl<E> RawComponent<E> for InputComponent {
-    fn render(&mut self) -> Vec<Shape> {
+    fn render(&mut self, _: Vec2<f32>) -> Vec<Shape> {
         vec![
             Shape::Text {
                 text: self.text.clone(),
@@ -50,7 +60,9 @@ impl<E> RawComponent<E> for InputComponent {
         ]
     }
 
-    fn set_mouse_status(&mut self, position: Option<Vec2<f32>>, pressed: bool) -> Vec<E> {
+    fn set_pointer_status(&mut self, _: Vec2<f32>, _: PointerId, position: Option<Vec2<f32>>,
+                          pressed: bool) -> Vec<E>
+    {
         if position.is_some() && pressed {
             
         }
@@ -58,15 +70,29 @@ impl<E> RawComponent<E> for InputComponent {
         Vec::with_capacity(0)
     }
 
-    fn hit_test(&mut self, pos: Vec2<f32>) -> bool {
-        pos.x >= 0.0 && pos.x < RawComponent::<E>::get_width(self) && pos.y >= 0.0 && pos.y < self.em
+    fn send_character(&mut self, _: Vec2<f32>, position: Option<Vec2<f32>>, character: char)
+                      -> Vec<E>
+    {
+        if position.is_some() {
+            if character == '\x08' {
+                self.text.pop();
+            } else if !character.is_control() {
+                self.text.push(character);
+            }
+        }
+
+        Vec::with_capacity(0)
+    }
+
+    fn hit_test(&mut self, size: Vec2<f32>, pos: Vec2<f32>) -> bool {
+        pos.x >= 0.0 && pos.x < size.x && pos.y >= 0.0 && pos.y < size.y
     }
 
-    fn get_width(&mut self) -> f32 {
-        self.em * self.text.len() as f32
+    fn find_test_id(&mut self, id: &str) -> Option<Vec<usize>> {
+        self.test_id.find(id)
     }
 
-    fn get_height(&mut self) -> f32 {
-        self.em
+    fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+        constraints.constrain(Vec2::new(self.em * self.text.len() as f32, self.em))
     }
 }
@@ -1,7 +1,9 @@
 pub use self::button::ButtonComponent;
 pub use self::input::InputComponent;
+pub use self::scroll::ScrollComponent;
 pub use self::text::TextComponent;
 
 pub mod button;
 pub mod input;
+pub mod scroll;
 pub mod text;
@@ -0,0 +1,585 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::default::Default;
+use std::f32;
+use nalgebra::Vec2;
+
+use drag::{DragEvent, DragStart};
+use shape::Shape;
+use component::{BoxConstraints, MouseButton, PointerEvent, PointerId, RawComponent, ScrollDelta};
+
+/// Shows a part of a content that can be larger than itself, and scrolls it with the mouse
+/// wheel or by dragging it.
+///
+/// The content is the only child of this component, at index `0`. Its shapes are clipped to
+/// the area of the scroll component.
+pub struct ScrollComponent<T> {
+    content: T,
+    /// Distance between the top-left hand corner of the content and the top-left hand corner
+    /// of the visible area.
+    position: Vec2<f32>,
+    horizontal: bool,
+    vertical: bool,
+    scrollbars: bool,
+    scrollbar_color: [f32; 3],
+    scrollbar_thickness: f32,
+    line_height: f32,
+    drag_to_scroll: bool,
+    pointers: HashMap<PointerId, PointerState>,
+}
+
+/// State of the scroll component in regards to one pointer.
+#[derive(Default)]
+struct PointerState {
+    pressed: bool,
+    /// Last position of the pointer if it is dragging the content.
+    drag: Option<Vec2<f32>>,
+}
+
+impl<T> ScrollComponent<T> {
+    /// Builds a component that scrolls the content vertically.
+    pub fn new(content: T) -> ScrollComponent<T> {
+        ScrollComponent {
+            content: content,
+            position: Vec2::new(0.0, 0.0),
+            horizontal: false,
+            vertical: true,
+            scrollbars: true,
+            scrollbar_color: [0.5, 0.5, 0.5],
+            scrollbar_thickness: 6.0,
+            line_height: 40.0,
+            drag_to_scroll: true,
+            pointers: HashMap::new(),
+        }
+    }
+
+    /// Gives an immutable access to the content.
+    pub fn get_content(&self) -> &T {
+        &self.content
+    }
+
+    /// Gives a mutable access to the content.
+    pub fn get_mut_content(&mut self) -> &mut T {
+        &mut self.content
+    }
+
+    /// Returns the distance between the top-left hand corner of the content and the top-left
+    /// hand corner of the visible area. Both coordinates are positive, `y` going down.
+    pub fn get_scroll_position(&self) -> Vec2<f32> {
+        self.position
+    }
+
+    /// Sets the distance between the top-left hand corner of the content and the top-left hand
+    /// corner of the visible area.
+    ///
+    /// The size of the content is only known once the component is laid out, so the position
+    /// is clamped to it during the next layout and `get_scroll_position` only returns the
+    /// clamped position afterwards.
+    pub fn set_scroll_position(&mut self, position: Vec2<f32>) {
+        self.position = position;
+    }
+
+    /// Sets along which axes the content can be scrolled. Along the other axes, the content
+    /// has the size of the scroll component.
+    pub fn set_directions(&mut self, horizontal: bool, vertical: bool) {
+        self.horizontal = horizontal;
+        self.vertical = vertical;
+    }
+
+    /// Sets whether scrollbars are drawn over the content when it is larger than the scroll
+    /// component. They are drawn by default.
+    pub fn set_scrollbars(&mut self, scrollbars: bool) {
+        self.scrollbars = scrollbars;
+    }
+
+    /// Sets the color of the scrollbars.
+    pub fn set_scrollbar_color(&mut self, color: [f32; 3]) {
+        self.scrollbar_color = color;
+    }
+
+    /// Sets the distance by which one line of a mouse wheel scrolls the content.
+    pub fn set_line_height(&mut self, height: f32) {
+        self.line_height = height;
+    }
+
+    /// Sets whether pressing a pointer over the content then moving it scrolls the content. The
+    /// default is `true`.
+    ///
+    /// When the pointer is pressed where nothing of the content is under it, this component
+    /// captures it so that the content keeps scrolling when the pointer leaves the component.
+    pub fn set_drag_to_scroll(&mut self, drag_to_scroll: bool) {
+        self.drag_to_scroll = drag_to_scroll;
+    }
+
+    /// Moves the visible area by the given distance, and returns true if it moved.
+    fn scroll_by<E>(&mut self, size: Vec2<f32>, delta: Vec2<f32>) -> bool
+        where T: RawComponent<E>
+    {
+        let before = self.position;
+        self.position = self.position + delta;
+        self.layout(size);
+        self.position != before
+    }
+
+    /// Clamps the scroll position, and returns the size of the content and its position
+    /// relative to this component.
+    fn layout<E>(&mut self, size: Vec2<f32>) -> (Vec2<f32>, Vec2<f32>) where T: RawComponent<E> {
+        let content = self.content.get_size(&BoxConstraints {
+            min: size,
+            max: Vec2::new(if self.horizontal { f32::INFINITY } else { size.x },
+                           if self.vertical { f32::INFINITY } else { size.y }),
+        });
+
+        let max = Vec2::new((content.x - size.x).max(0.0), (content.y - size.y).max(0.0));
+        self.position = Vec2::new(self.position.x.max(0.0).min(max.x),
+                                  self.position.y.max(0.0).min(max.y));
+
+        (content, Vec2::new(-self.position.x, size.y - content.y + self.position.y))
+    }
+
+    /// Scrolls the content if the pointer drags it, then tells the content where the pointer is.
+    ///
+    /// `drag_position` is the position that moves the content. Unlike `position`, it can be
+    /// outside of the component if the component captured the pointer.
+    fn move_pointer<E>(&mut self, size: Vec2<f32>, pointer: PointerId,
+                       drag_position: Option<Vec2<f32>>, position: Option<Vec2<f32>>,
+                       pressed: bool) -> Vec<E>
+        where T: RawComponent<E>
+    {
+        let drag_to_scroll = self.drag_to_scroll;
+        let delta = {
+            let state = self.pointers.entry(pointer).or_insert_with(Default::default);
+
+            let delta = match (state.drag, drag_position) {
+                (Some(last), Some(position)) if pressed => {
+                    Some(Vec2::new(last.x - position.x, position.y - last.y))
+                },
+                _ => None,
+            };
+
+            // a drag starts when the pointer is pressed over the component, and goes on as long
+            // as the pointer stays pressed over it or captured by it
+            state.drag = match drag_position {
+                Some(position) if pressed && state.drag.is_some() => Some(position),
+                Some(position) if pressed && !state.pressed && drag_to_scroll => Some(position),
+                _ => None,
+            };
+
+            state.pressed = pressed;
+            delta
+        };
+
+        if drag_position.is_none() && !pressed {
+            self.pointers.remove(&pointer);
+        }
+
+        if let Some(delta) = delta {
+            self.scroll_by(size, delta);
+        }
+
+        let (content, offset) = self.layout(size);
+        self.content.set_pointer_status(content, pointer, position.map(|p| p - offset), pressed)
+    }
+
+    /// Returns the shapes of the scrollbars.
+    fn render_scrollbars(&self, size: Vec2<f32>, content: Vec2<f32>) -> Vec<Shape> {
+        let mut shapes = Vec::with_capacity(0);
+        let thickness = self.scrollbar_thickness;
+
+        if content.y > size.y {
+            let length = size.y * size.y / content.y;
+            let top = size.y - self.position.y * size.y / content.y;
+            shapes.push(Shape::Rectangle {
+                from: Vec2::new(size.x - thickness, top - length),
+                to: Vec2::new(size.x, top),
+                color: self.scrollbar_color,
+            });
+        }
+
+        if content.x > size.x {
+            let length = size.x * size.x / content.x;
+            let left = self.position.x * size.x / content.x;
+            shapes.push(Shape::Rectangle {
+                from: Vec2::new(left, 0.0),
+                to: Vec2::new(left + length, thickness),
+                color: self.scrollbar_color,
+            });
+        }
+
+        shapes
+    }
+}
+
+/// Returns the position if it is within the area of the given size.
+fn inside(size: Vec2<f32>, position: Option<Vec2<f32>>) -> Option<Vec2<f32>> {
+    position.and_then(|pos| {
+        if pos.x >= 0.0 && pos.x < size.x && pos.y >= 0.0 && pos.y < size.y {
+            Some(pos)
+        } else {
+            None
+        }
+    })
+}
+
+/// Adds the index of the content at the start of a path.
+fn prefix(path: Vec<usize>) -> Vec<usize> {
+    let mut result = Vec::with_capacity(path.len() + 1);
+    result.push(0);
+    result.extend(path);
+    result
+}
+
+impl<T, E> RawComponent<E> for ScrollComponent<T> where T: RawComponent<E> {
+    fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
+        let (content, offset) = self.layout(size);
+
+        let mut shapes = self.content.render(content).into_iter()
+                             .map(|s| s.translate(offset).clip(Vec2::new(0.0, 0.0), size))
+                             .collect::<Vec<_>>();
+
+        if self.scrollbars {
+            shapes.extend(self.render_scrollbars(size, content));
+        }
+
+        shapes
+    }
+
+    fn set_pointer_status(&mut self, size: Vec2<f32>, pointer: PointerId,
+                          position: Option<Vec2<f32>>, pressed: bool) -> Vec<E>
+    {
+        let position = inside(size, position);
+        self.move_pointer(size, pointer, position, position, pressed)
+    }
+
+    fn hit_test(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> bool {
+        inside(size, Some(position)).is_some()
+    }
+
+    fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+        let content = self.content.get_size(&BoxConstraints {
+            min: Vec2::new(0.0, 0.0),
+            max: Vec2::new(if self.horizontal { f32::INFINITY } else { constraints.max.x },
+                           if self.vertical { f32::INFINITY } else { constraints.max.y }),
+        });
+
+        constraints.constrain(content)
+    }
+
+    fn set_captured_pointer_status(&mut self, size: Vec2<f32>, path: &[usize],
+                                   pointer: PointerId, position: Vec2<f32>, pressed: bool)
+                                   -> Vec<E>
+    {
+        if path.is_empty() {
+            let inside = inside(size, Some(position));
+            return self.move_pointer(size, pointer, Some(position), inside, pressed);
+        }
+
+        let (content, offset) = self.layout(size);
+        self.content.set_captured_pointer_status(content, &path[1 ..], pointer,
+                                                 position - offset, pressed)
+    }
+
+    fn get_capture_path(&mut self, path: &[usize], pointer: PointerId, button: MouseButton)
+                        -> Option<Vec<usize>>
+    {
+        // the content keeps scrolling when the pointer that drags it leaves the component
+        if path.is_empty() {
+            if self.drag_to_scroll && button == MouseButton::Left {
+                return Some(Vec::new());
+            } else {
+                return None;
+            }
+        }
+
+        self.content.get_capture_path(&path[1 ..], pointer, button).map(prefix)
+    }
+
+    fn get_drag_start(&mut self, path: &[usize], pointer: PointerId)
+                      -> Option<(Vec<usize>, DragStart)>
+    {
+        if path.is_empty() {
+            return None;
+        }
+
+        let start = self.content.get_drag_start(&path[1 ..], pointer);
+
+        // the content doesn't scroll while one of its children is dragged
+        if start.is_some() {
+            if let Some(state) = self.pointers.get_mut(&pointer) {
+                state.drag = None;
+            }
+        }
+
+        start.map(|(path, start)| (prefix(path), start))
+    }
+
+    fn get_drop_target(&mut self, path: &[usize], payload: &Any) -> Option<Vec<usize>> {
+        if path.is_empty() {
+            return None;
+        }
+
+        self.content.get_drop_target(&path[1 ..], payload).map(prefix)
+    }
+
+    fn drag_event(&mut self, size: Vec2<f32>, path: &[usize], position: Vec2<f32>,
+                  event: DragEvent) -> Vec<E>
+    {
+        if path.is_empty() {
+            return Vec::with_capacity(0);
+        }
+
+        let (content, offset) = self.layout(size);
+        self.content.drag_event(content, &path[1 ..], position - offset, event)
+    }
+
+    fn pointer_event(&mut self, size: Vec2<f32>, path: &[usize], pointer: PointerId,
+                     position: Vec2<f32>, event: PointerEvent) -> Vec<E>
+    {
+        if path.is_empty() {
+            return Vec::with_capacity(0);
+        }
+
+        let (content, offset) = self.layout(size);
+        self.content.pointer_event(content, &path[1 ..], pointer, position - offset, event)
+    }
+
+    fn set_hovered_paths(&mut self, old: &[&[usize]], new: &[&[usize]]) -> Vec<E> {
+        let old = old.iter().filter(|p| !p.is_empty()).map(|p| &p[1 ..]).collect::<Vec<_>>();
+        let new = new.iter().filter(|p| !p.is_empty()).map(|p| &p[1 ..]).collect::<Vec<_>>();
+        self.content.set_hovered_paths(&old, &new)
+    }
+
+    fn hit_path(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> Option<Vec<usize>> {
+        if inside(size, Some(position)).is_none() {
+            return None;
+        }
+
+        let (content, offset) = self.layout(size);
+        match self.content.hit_path(content, position - offset) {
+            Some(path) => Some(prefix(path)),
+            None => Some(Vec::new()),
+        }
+    }
+
+    fn scroll(&mut self, size: Vec2<f32>, position: Vec2<f32>, delta: ScrollDelta)
+              -> (bool, Vec<E>)
+    {
+        if inside(size, Some(position)).is_none() {
+            return (false, Vec::with_capacity(0));
+        }
+
+        let (content, offset) = self.layout(size);
+        let (handled, events) = self.content.scroll(content, position - offset, delta);
+        if handled {
+            return (true, events);
+        }
+
+        let delta = match delta {
+            ScrollDelta::Lines(lines) => {
+                Vec2::new(lines.x * self.line_height, lines.y * self.line_height)
+            },
+            ScrollDelta::Pixels(distance) | ScrollDelta::Logical(distance) => distance,
+        };
+
+        // an unused scroll is left to the parent, for example an enclosing scroll component
+        let handled = self.scroll_by(size, Vec2::new(-delta.x, delta.y));
+        (handled, events)
+    }
+
+    fn send_character(&mut self, size: Vec2<f32>, position: Option<Vec2<f32>>, character: char)
+                      -> Vec<E>
+    {
+        let (content, offset) = self.layout(size);
+        let position = inside(size, position).map(|p| p - offset);
+        self.content.send_character(content, position, character)
+    }
+
+    fn find_test_id(&mut self, id: &str) -> Option<Vec<usize>> {
+        self.content.find_test_id(id).map(prefix)
+    }
+
+    fn tick(&mut self, elapsed: f32) -> Vec<E> {
+        self.content.tick(elapsed)
+    }
+
+    fn is_animating(&mut self) -> bool {
+        self.content.is_animating()
+    }
+
+    fn get_child_area(&mut self, size: Vec2<f32>, path: &[usize])
+                      -> Option<(Vec2<f32>, Vec2<f32>)>
+    {
+        if path.is_empty() {
+            return Some((Vec2::new(0.0, 0.0), size));
+        }
+
+        let (content, offset) = self.layout(size);
+        self.content.get_child_area(content, &path[1 ..])
+                    .map(|(bottom_left, top_right)| (bottom_left + offset, top_right + offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vec2;
+
+    use Component;
+    use Layout;
+    use component::{BoxConstraints, MouseButton, PointerId, RawComponent, ScrollDelta};
+    use shape::Shape;
+    use super::ScrollComponent;
+
+    /// A block of 100 by 100.
+    struct Block;
+
+    impl RawComponent<()> for Block {
+        fn render(&mut self, size: Vec2<f32>) -> Vec<Shape> {
+            vec![Shape::Rectangle { from: Vec2::new(0.0, 0.0), to: size, color: [0.0; 3] }]
+        }
+
+        fn set_pointer_status(&mut self, _: Vec2<f32>, _: PointerId, _: Option<Vec2<f32>>,
+                              _: bool) -> Vec<()>
+        {
+            Vec::with_capacity(0)
+        }
+
+        fn hit_test(&mut self, size: Vec2<f32>, position: Vec2<f32>) -> bool {
+            position.x >= 0.0 && position.x < size.x && position.y >= 0.0 && position.y < size.y
+        }
+
+        fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+            constraints.constrain(Vec2::new(100.0, 100.0))
+        }
+    }
+
+    /// Three blocks on top of each other.
+    struct Column {
+        blocks: [Block; 3],
+    }
+
+    impl Component for Column {
+        type EmittedEvent = ();
+        type ReceivedEvent = ();
+
+        fn get_layout(&mut self) -> Layout<()> {
+            let (bottom, rest) = self.blocks.split_at_mut(1);
+            let (middle, top) = rest.split_at_mut(1);
+            Layout::VerticalBox(vec![&mut bottom[0], &mut middle[0], &mut top[0]], 0.0)
+        }
+    }
+
+    /// A scroll component of 100 by 100 around a `Column`, showing its top block.
+    fn scroll() -> (ScrollComponent<Column>, Vec2<f32>) {
+        let mut scroll = ScrollComponent::new(Column { blocks: [Block, Block, Block] });
+        scroll.set_scrollbars(false);
+        (scroll, Vec2::new(100.0, 100.0))
+    }
+
+    /// Uses the mouse wheel over the center of the component, and returns whether it scrolled.
+    fn wheel(scroll: &mut ScrollComponent<Column>, delta: ScrollDelta) -> bool {
+        let size = Vec2::new(100.0, 100.0);
+        RawComponent::<()>::scroll(scroll, size, Vec2::new(50.0, 50.0), delta).0
+    }
+
+    /// Moves a finger, which is captured by the component if `captured` is true.
+    fn touch(scroll: &mut ScrollComponent<Column>, x: f32, y: f32, pressed: bool, captured: bool) {
+        let size = Vec2::new(100.0, 100.0);
+        let (pointer, position) = (PointerId::Touch(1), Vec2::new(x, y));
+
+        if captured {
+            RawComponent::<()>::set_captured_pointer_status(scroll, size, &[], pointer, position,
+                                                            pressed);
+        } else {
+            RawComponent::<()>::set_pointer_status(scroll, size, pointer, Some(position), pressed);
+        }
+    }
+
+    #[test]
+    fn wheel_scrolls_until_the_end() {
+        let (mut scroll, _) = scroll();
+
+        assert!(wheel(&mut scroll, ScrollDelta::Lines(Vec2::new(0.0, 1.0))));
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 40.0));
+
+        wheel(&mut scroll, ScrollDelta::Logical(Vec2::new(0.0, 30.0)));
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 70.0));
+
+        // the content is 200 higher than the component
+        wheel(&mut scroll, ScrollDelta::Lines(Vec2::new(0.0, 10.0)));
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 200.0));
+
+        // a scroll that can't move the content is left to the parent
+        assert!(!wheel(&mut scroll, ScrollDelta::Lines(Vec2::new(0.0, 1.0))));
+    }
+
+    #[test]
+    fn set_position_is_clamped_by_the_layout() {
+        let (mut scroll, size) = scroll();
+        scroll.set_scroll_position(Vec2::new(-20.0, 1000.0));
+        RawComponent::<()>::render(&mut scroll, size);
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 200.0));
+    }
+
+    #[test]
+    fn drag_scrolls_and_captures() {
+        let (mut scroll, _) = scroll();
+        let pointer = PointerId::Touch(1);
+
+        touch(&mut scroll, 50.0, 20.0, true, false);
+        let capture = RawComponent::<()>::get_capture_path(&mut scroll, &[], pointer,
+                                                           MouseButton::Left);
+        assert_eq!(capture, Some(Vec::new()));
+
+        // moving the pointer up shows the blocks below
+        touch(&mut scroll, 50.0, 50.0, true, false);
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 30.0));
+
+        // the drag goes on outside of the component once it is captured
+        touch(&mut scroll, 50.0, 150.0, true, true);
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 130.0));
+
+        touch(&mut scroll, 50.0, 150.0, false, true);
+        touch(&mut scroll, 50.0, 90.0, false, false);
+        assert_eq!(scroll.get_scroll_position(), Vec2::new(0.0, 130.0));
+
+        scroll.set_drag_to_scroll(false);
+        let capture = RawComponent::<()>::get_capture_path(&mut scroll, &[], pointer,
+                                                           MouseButton::Left);
+        assert_eq!(capture, None);
+    }
+
+    #[test]
+    fn hit_path_follows_the_scroll_position() {
+        let (mut scroll, size) = scroll();
+        let center = Vec2::new(50.0, 50.0);
+        assert_eq!(RawComponent::<()>::hit_path(&mut scroll, size, center), Some(vec![0, 2]));
+
+        scroll.set_scroll_position(Vec2::new(0.0, 100.0));
+        assert_eq!(RawComponent::<()>::hit_path(&mut scroll, size, center), Some(vec![0, 1]));
+
+        let area = RawComponent::<()>::get_child_area(&mut scroll, size, &[0, 1]);
+        assert_eq!(area, Some((Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0))));
+        assert_eq!(RawComponent::<()>::hit_path(&mut scroll, size, Vec2::new(50.0, 150.0)), None);
+    }
+
+    #[test]
+    fn content_is_clipped() {
+        let (mut scroll, size) = scroll();
+        scroll.set_scroll_position(Vec2::new(0.0, 50.0));
+
+        let shapes = RawComponent::<()>::render(&mut scroll, size);
+        assert_eq!(shapes.len(), 3);
+        for shape in shapes.iter() {
+            match shape {
+                &Shape::Clipped { from, to, .. } => {
+                    assert_eq!((from, to), (Vec2::new(0.0, 0.0), size));
+                },
+                _ => panic!("The shape {:?} isn't clipped", shape),
+            }
+        }
+
+        // only the halves of the two top blocks are visible
+        let visible = shapes.into_iter().filter_map(|s| s.apply_clip()).collect::<Vec<_>>();
+        assert_eq!(visible.len(), 2);
+    }
+}
@@ -3,21 +3,26 @@ use std::any::Any;
 use std::default::Default;
 use shape::{Font, Shape};
 
-use component::RawComponent;
+use component::{BoxConstraints, PointerId, RawComponent};
+use testing::TestId;
 
 #[derive(Default)]
 pub struct TextComponent {
     text: String,
     font: Font,
     em: f32,
+    test_id: TestId,
 }
 
 impl TextComponent {
+    /// Builds the component. `em` is the height of the text in the unit of the `Ui`, usually
+    /// logical pixels.
     pub fn new(text: String, font: Font, em: f32) -> TextComponent {
         TextComponent {
             text: text,
             font: font,
             em: em,
+            test_id: Default::default(),
         }
     }
 
@@ -33,6 +38,11 @@ impl TextComponent {
         self.em = em;
     }
 
+    /// Sets the identifier that tests can use to locate this component.
+    pub fn set_test_id(&mut self, id: String) {
+        self.test_id.set(id);
+    }
+
     fn get_dimensions(&self) -> Vec2<f32> {
         // FIXME:
         let width = self.em * self.text.len() as f32;
@@ -41,7 +51,7 @@ impl TextComponent {
 }
 
 impl<E> RawComponent<E> for TextComponent {
-    fn render(&mut self) -> Vec<Shape> {
+    fn render(&mut self, _: Vec2<f32>) -> Vec<Shape> {
         vec![
             Shape::Text {
                 text: self.text.clone(),
@@ -52,19 +62,21 @@ impl<E> RawComponent<E> for TextComponent {
         ]
     }
 
-    fn set_mouse_status(&mut self, _: Option<Vec2<f32>>, _: bool) -> Vec<E> {
+    fn set_pointer_status(&mut self, _: Vec2<f32>, _: PointerId, _: Option<Vec2<f32>>, _: bool)
+                          -> Vec<E>
+    {
         Vec::with_capacity(0)
     }
 
-    fn hit_test(&mut self, pos: Vec2<f32>) -> bool {
-        pos.x >= 0.0 && pos.x < RawComponent::<E>::get_width(self) && pos.y >= 0.0 && pos.y < self.em
+    fn hit_test(&mut self, size: Vec2<f32>, pos: Vec2<f32>) -> bool {
+        pos.x >= 0.0 && pos.x < size.x && pos.y >= 0.0 && pos.y < size.y
     }
 
-    fn get_width(&mut self) -> f32 {
-        self.em * self.text.len() as f32
+    fn find_test_id(&mut self, id: &str) -> Option<Vec<usize>> {
+        self.test_id.find(id)
     }
 
-    fn get_height(&mut self) -> f32 {
-        self.em
+    fn get_size(&mut self, constraints: &BoxConstraints) -> Vec2<f32> {
+        constraints.constrain(self.get_dimensions())
     }
 }
@@ -0,0 +1,736 @@
+//! Recording of the input of a `Ui` and deterministic replay.
+//!
+//! A `Recorder` wraps around a `Ui` and remembers every input it receives, alongside the events
+//! and shapes that each input produced. The resulting `Recording` can be saved to a file, then
+//! replayed on a fresh `Ui` in order to check that it behaves the same way frame by frame.
+//!
+//! Modifying the main component in other ways than through the recorder will make the replay
+//! diverge from the recording.
+
+use std::fmt::{self, Debug};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use nalgebra::Vec2;
+
+/// Returns `None` from the current function if the expression is `None`.
+macro_rules! try_opt {
+    ($e:expr) => (match $e { Some(v) => v, None => return None })
+}
+
+use component::{MouseButton, PointerId, RawComponent, ScrollDelta};
+use snapshot;
+use ui::{ClickSettings, Origin, Ui, Unit};
+
+/// Settings of a `Ui` when a recording starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitialState {
+    /// Dimensions of the viewport.
+    pub viewport: Vec2<u32>,
+    /// Number of physical pixels per logical pixel.
+    pub dpi_factor: f32,
+    /// Unit of the components.
+    pub unit: Unit,
+    /// Corner of the viewport where the main component has its origin.
+    pub origin: Origin,
+    /// Thresholds used to detect clicks.
+    pub click_settings: ClickSettings,
+}
+
+/// An input passed to a `Ui`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// Initial state of the `Ui` when the recording started.
+    Start(InitialState),
+    /// A call to `set_viewport`.
+    SetViewport(Vec2<u32>),
+    /// A call to `set_dpi_factor`.
+    SetDpiFactor(f32),
+    /// A call to `set_unit`.
+    SetUnit(Unit),
+    /// A call to `set_origin`.
+    SetOrigin(Origin),
+    /// A call to `set_mouse_position`.
+    SetMousePosition(Option<Vec2<u32>>),
+    /// A call to `set_mouse_pressed`.
+    SetMousePressed(bool),
+    /// A call to `set_mouse_button_pressed`.
+    SetMouseButtonPressed(MouseButton, bool),
+    /// A call to `set_pointer_position`.
+    SetPointerPosition(PointerId, Option<Vec2<u32>>),
+    /// A call to `set_pointer_button_pressed`.
+    SetPointerButtonPressed(PointerId, MouseButton, bool),
+    /// A call to `remove_pointer`.
+    RemovePointer(PointerId),
+    /// A call to `tick`.
+    Tick(f32),
+    /// A call to `scroll`.
+    Scroll(ScrollDelta),
+    /// A call to `send_character`.
+    SendCharacter(char),
+}
+
+/// An input and what the `Ui` produced in response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Number of seconds between the start of the recording and this input.
+    pub time: f64,
+    /// The input that was passed.
+    pub input: Input,
+    /// `Debug` representation of the events produced by the input.
+    pub events: Vec<String>,
+    /// Shapes after the input, in the format of the `snapshot` module.
+    pub shapes: String,
+}
+
+/// List of frames of a recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// The frames, in chronological order.
+    pub frames: Vec<Frame>,
+}
+
+/// Wraps around a `Ui` and records its input.
+pub struct Recorder<T, E> {
+    ui: Ui<T, E>,
+    start: Instant,
+    frames: Vec<Frame>,
+    events: Vec<E>,
+}
+
+impl<T, E> Recorder<T, E> where T: RawComponent<E>, E: Debug {
+    /// Starts recording the input of a `Ui`.
+    pub fn new(ui: Ui<T, E>) -> Recorder<T, E> {
+        let state = InitialState {
+            viewport: ui.get_viewport(),
+            dpi_factor: ui.get_dpi_factor(),
+            unit: ui.get_unit(),
+            origin: ui.get_origin(),
+            click_settings: ui.get_click_settings().clone(),
+        };
+
+        let mut recorder = Recorder {
+            ui: ui,
+            start: Instant::now(),
+            frames: Vec::new(),
+            events: Vec::new(),
+        };
+
+        recorder.record(Input::Start(state));
+        recorder
+    }
+
+    /// Gives access to the `Ui`.
+    pub fn get_ui(&self) -> &Ui<T, E> {
+        &self.ui
+    }
+
+    /// Sets the viewport of the user interface.
+    pub fn set_viewport(&mut self, dimensions: Vec2<u32>) {
+        self.ui.set_viewport(dimensions);
+        self.record(Input::SetViewport(dimensions));
+    }
+
+    /// Sets the number of physical pixels per logical pixel.
+    pub fn set_dpi_factor(&mut self, factor: f32) {
+        self.ui.set_dpi_factor(factor);
+        self.record(Input::SetDpiFactor(factor));
+    }
+
+    /// Sets the unit of the components.
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.ui.set_unit(unit);
+        self.record(Input::SetUnit(unit));
+    }
+
+    /// Sets the corner of the viewport where the main component has its origin.
+    pub fn set_origin(&mut self, origin: Origin) {
+        self.ui.set_origin(origin);
+        self.record(Input::SetOrigin(origin));
+    }
+
+    /// Changes the position of the mouse over the UI.
+    pub fn set_mouse_position(&mut self, position: Option<Vec2<u32>>) {
+        self.ui.set_mouse_position(position);
+        self.record(Input::SetMousePosition(position));
+    }
+
+    /// Sets whether the mouse is pressed.
+    pub fn set_mouse_pressed(&mut self, pressed: bool) {
+        self.ui.set_mouse_pressed(pressed);
+        self.record(Input::SetMousePressed(pressed));
+    }
+
+    /// Sets whether a button of the mouse is pressed.
+    pub fn set_mouse_button_pressed(&mut self, button: MouseButton, pressed: bool) {
+        self.ui.set_mouse_button_pressed(button, pressed);
+        self.record(Input::SetMouseButtonPressed(button, pressed));
+    }
+
+    /// Changes the position of a pointer over the UI.
+    pub fn set_pointer_position(&mut self, pointer: PointerId, position: Option<Vec2<u32>>) {
+        self.ui.set_pointer_position(pointer, position);
+        self.record(Input::SetPointerPosition(pointer, position));
+    }
+
+    /// Sets whether a button of a pointer is pressed.
+    pub fn set_pointer_button_pressed(&mut self, pointer: PointerId, button: MouseButton,
+                                      pressed: bool)
+    {
+        self.ui.set_pointer_button_pressed(pointer, button, pressed);
+        self.record(Input::SetPointerButtonPressed(pointer, button, pressed));
+    }
+
+    /// Removes a pointer from the UI.
+    pub fn remove_pointer(&mut self, pointer: PointerId) {
+        self.ui.remove_pointer(pointer);
+        self.record(Input::RemovePointer(pointer));
+    }
+
+    /// Advances the time of the user interface by the given number of seconds.
+    pub fn tick(&mut self, elapsed: f32) {
+        self.ui.tick(elapsed);
+        self.record(Input::Tick(elapsed));
+    }
+
+    /// Tells the component under the mouse that the mouse wheel was used.
+    pub fn scroll(&mut self, delta: ScrollDelta) {
+        self.ui.scroll(delta);
+        self.record(Input::Scroll(delta));
+    }
+
+    /// Sends a character typed on the keyboard to the component under the mouse.
+    pub fn send_character(&mut self, character: char) {
+        self.ui.send_character(character);
+        self.record(Input::SendCharacter(character));
+    }
+
+    /// Returns the list of events produced by the main component since the last call to this
+    /// function.
+    pub fn poll_events(&mut self) -> Vec<E> {
+        ::std::mem::replace(&mut self.events, Vec::new())
+    }
+
+    /// Stops the recording.
+    pub fn into_recording(self) -> Recording {
+        Recording { frames: self.frames }
+    }
+
+    fn record(&mut self, input: Input) {
+        let elapsed = self.start.elapsed();
+        let events = self.ui.poll_events();
+
+        self.frames.push(Frame {
+            time: elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9,
+            input: input,
+            events: events.iter().map(|e| format!("{:?}", e)).collect(),
+            shapes: snapshot::serialize(self.ui.draw()),
+        });
+
+        self.events.extend(events);
+    }
+}
+
+/// Difference between a recording and its replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// Index of the first frame that differs.
+    pub frame: usize,
+    /// Human-readable description of the difference.
+    pub description: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Frame #{} differs:\n{}", self.frame, self.description)
+    }
+}
+
+impl Recording {
+    /// Loads a recording from a file.
+    pub fn load<P>(path: P) -> io::Result<Recording> where P: AsRef<Path> {
+        let mut content = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut content));
+        Recording::parse(&content)
+    }
+
+    /// Saves the recording to a file.
+    pub fn save<P>(&self, path: P) -> io::Result<()> where P: AsRef<Path> {
+        try!(File::create(path)).write_all(self.to_string().as_bytes())
+    }
+
+    /// Parses the content of a file written by `save`.
+    pub fn parse(content: &str) -> io::Result<Recording> {
+        let mut frames: Vec<Frame> = Vec::new();
+
+        for (line_num, line) in content.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               format!("Invalid recording at line {}: {}", line_num + 1, line))
+            };
+
+            if line.starts_with("shape ") {
+                let frame = try!(frames.last_mut().ok_or_else(&invalid));
+                frame.shapes.push_str(&line[6 ..]);
+                frame.shapes.push('\n');
+
+            } else if line.starts_with("event ") {
+                let frame = try!(frames.last_mut().ok_or_else(&invalid));
+                frame.events.push(line[6 ..].to_string());
+
+            } else if line.starts_with("frame ") {
+                let words = line[6 ..].split(' ').collect::<Vec<_>>();
+                if words.len() < 2 {
+                    return Err(invalid());
+                }
+
+                let time = try!(words[0].parse().map_err(|_| invalid()));
+                let input = try!(parse_input(&words[1 ..]).ok_or_else(&invalid));
+                frames.push(Frame { time: time, input: input, events: Vec::new(),
+                                    shapes: String::new() });
+
+            } else if !line.is_empty() {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Recording { frames: frames })
+    }
+
+    /// Feeds the recorded input to a `Ui` and compares the events and shapes it produces with
+    /// the recorded ones.
+    ///
+    /// The `Ui` is expected to have been built the same way as the one that was recorded. Its
+    /// settings are reset to the ones that the recorded `Ui` had at the start.
+    pub fn replay<T, E>(&self, ui: &mut Ui<T, E>) -> Result<(), Mismatch>
+        where T: RawComponent<E>, E: Debug
+    {
+        ui.poll_events();
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            match frame.input {
+                Input::Start(ref state) => {
+                    ui.set_click_settings(state.click_settings.clone());
+                    ui.set_unit(state.unit);
+                    ui.set_origin(state.origin);
+                    ui.set_dpi_factor(state.dpi_factor);
+                    ui.set_viewport(state.viewport);
+                },
+                Input::SetViewport(viewport) => ui.set_viewport(viewport),
+                Input::SetDpiFactor(factor) => ui.set_dpi_factor(factor),
+                Input::SetUnit(unit) => ui.set_unit(unit),
+                Input::SetOrigin(origin) => ui.set_origin(origin),
+                Input::SetMousePosition(position) => ui.set_mouse_position(position),
+                Input::SetMousePressed(pressed) => ui.set_mouse_pressed(pressed),
+                Input::SetMouseButtonPressed(button, pressed) => {
+                    ui.set_mouse_button_pressed(button, pressed)
+                },
+                Input::SetPointerPosition(pointer, position) => {
+                    ui.set_pointer_position(pointer, position)
+                },
+                Input::SetPointerButtonPressed(pointer, button, pressed) => {
+                    ui.set_pointer_button_pressed(pointer, button, pressed)
+                },
+                Input::RemovePointer(pointer) => ui.remove_pointer(pointer),
+                Input::Tick(elapsed) => ui.tick(elapsed),
+                Input::Scroll(delta) => ui.scroll(delta),
+                Input::SendCharacter(character) => ui.send_character(character),
+            }
+
+            let events = ui.poll_events().iter().map(|e| format!("{:?}", e)).collect::<Vec<_>>();
+            if events != frame.events {
+                return Err(Mismatch {
+                    frame: index,
+                    description: format!("Expected events {:?}, got {:?}", frame.events, events),
+                });
+            }
+
+            let shapes = snapshot::serialize(ui.draw());
+            if let Some(diff) = snapshot::diff(&frame.shapes, &shapes) {
+                return Err(Mismatch { frame: index, description: diff });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the recording in the format expected by `parse`.
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for frame in &self.frames {
+            try!(write!(f, "frame {:.6} ", frame.time));
+
+            try!(match frame.input {
+                Input::Start(ref state) => {
+                    let settings = &state.click_settings;
+                    writeln!(f, "start {} {} dpi_factor={} unit={} origin={} double_click_time={} \
+                                 click_distance={} long_press_time={}",
+                             state.viewport.x, state.viewport.y, state.dpi_factor,
+                             unit_name(state.unit), origin_name(state.origin),
+                             settings.double_click_time, settings.click_distance,
+                             settings.long_press_time)
+                },
+                Input::SetViewport(v) => writeln!(f, "viewport {} {}", v.x, v.y),
+                Input::SetDpiFactor(factor) => writeln!(f, "dpi_factor {}", factor),
+                Input::SetUnit(unit) => writeln!(f, "unit {}", unit_name(unit)),
+                Input::SetOrigin(origin) => writeln!(f, "origin {}", origin_name(origin)),
+                Input::SetMousePosition(Some(v)) => writeln!(f, "mouse_position {} {}", v.x, v.y),
+                Input::SetMousePosition(None) => writeln!(f, "mouse_position none"),
+                Input::SetMousePressed(pressed) => writeln!(f, "mouse_pressed {}", pressed),
+                Input::SetMouseButtonPressed(button, pressed) => {
+                    writeln!(f, "mouse_button_pressed {} {}", button_name(button), pressed)
+                },
+                Input::SetPointerPosition(pointer, Some(v)) => {
+                    writeln!(f, "pointer_position {} {} {}", pointer_name(pointer), v.x, v.y)
+                },
+                Input::SetPointerPosition(pointer, None) => {
+                    writeln!(f, "pointer_position {} none", pointer_name(pointer))
+                },
+                Input::SetPointerButtonPressed(pointer, button, pressed) => {
+                    writeln!(f, "pointer_button_pressed {} {} {}", pointer_name(pointer),
+                             button_name(button), pressed)
+                },
+                Input::RemovePointer(pointer) => {
+                    writeln!(f, "remove_pointer {}", pointer_name(pointer))
+                },
+                Input::Tick(elapsed) => writeln!(f, "tick {}", elapsed),
+                Input::Scroll(ScrollDelta::Lines(v)) => writeln!(f, "scroll lines {} {}", v.x, v.y),
+                Input::Scroll(ScrollDelta::Pixels(v)) => writeln!(f, "scroll pixels {} {}", v.x, v.y),
+                Input::Scroll(ScrollDelta::Logical(v)) => {
+                    writeln!(f, "scroll logical {} {}", v.x, v.y)
+                },
+                Input::SendCharacter(c) => writeln!(f, "character {}", c as u32),
+            });
+
+            for event in &frame.events {
+                try!(writeln!(f, "event {}", event));
+            }
+
+            for shape in frame.shapes.lines() {
+                try!(writeln!(f, "shape {}", shape));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of a pointer in the recording format.
+fn pointer_name(pointer: PointerId) -> String {
+    match pointer {
+        PointerId::Mouse => "mouse".to_string(),
+        PointerId::Touch(id) => format!("touch:{}", id),
+        PointerId::Pen(id) => format!("pen:{}", id),
+    }
+}
+
+/// Parses the name of a pointer written by `pointer_name`.
+fn parse_pointer(name: &str) -> Option<PointerId> {
+    if name == "mouse" {
+        Some(PointerId::Mouse)
+    } else if name.starts_with("touch:") {
+        name[6 ..].parse().ok().map(PointerId::Touch)
+    } else if name.starts_with("pen:") {
+        name[4 ..].parse().ok().map(PointerId::Pen)
+    } else {
+        None
+    }
+}
+
+/// Parses the name of a button written by `button_name`.
+fn parse_button(name: &str) -> Option<MouseButton> {
+    match name {
+        "left" => Some(MouseButton::Left),
+        "right" => Some(MouseButton::Right),
+        "middle" => Some(MouseButton::Middle),
+        _ => None,
+    }
+}
+
+/// Returns the name of a button in the recording format.
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+    }
+}
+
+/// Returns the name of a unit in the recording format.
+fn unit_name(unit: Unit) -> &'static str {
+    match unit {
+        Unit::Normalized => "normalized",
+        Unit::LogicalPixels => "logical_pixels",
+        Unit::PhysicalPixels => "physical_pixels",
+        Unit::NormalizedHeight => "normalized_height",
+    }
+}
+
+/// Parses the name of a unit written by `unit_name`.
+fn parse_unit(name: &str) -> Option<Unit> {
+    match name {
+        "normalized" => Some(Unit::Normalized),
+        "logical_pixels" => Some(Unit::LogicalPixels),
+        "physical_pixels" => Some(Unit::PhysicalPixels),
+        "normalized_height" => Some(Unit::NormalizedHeight),
+        _ => None,
+    }
+}
+
+/// Returns the name of an origin in the recording format.
+fn origin_name(origin: Origin) -> &'static str {
+    match origin {
+        Origin::BottomLeft => "bottom_left",
+        Origin::TopLeft => "top_left",
+    }
+}
+
+/// Parses the name of an origin written by `origin_name`.
+fn parse_origin(name: &str) -> Option<Origin> {
+    match name {
+        "bottom_left" => Some(Origin::BottomLeft),
+        "top_left" => Some(Origin::TopLeft),
+        _ => None,
+    }
+}
+
+/// Parses the words of a `start` input after the viewport. Settings that are missing keep
+/// their default value.
+fn parse_initial_state(viewport: Vec2<u32>, words: &[&str]) -> Option<InitialState> {
+    let mut state = InitialState {
+        viewport: viewport,
+        dpi_factor: 1.0,
+        unit: Default::default(),
+        origin: Default::default(),
+        click_settings: Default::default(),
+    };
+
+    for word in words {
+        let pos = try_opt!(word.find('='));
+        let value = &word[pos + 1 ..];
+
+        match &word[.. pos] {
+            "dpi_factor" => state.dpi_factor = try_opt!(value.parse().ok()),
+            "unit" => state.unit = try_opt!(parse_unit(value)),
+            "origin" => state.origin = try_opt!(parse_origin(value)),
+            "double_click_time" => {
+                state.click_settings.double_click_time = try_opt!(value.parse().ok())
+            },
+            "click_distance" => state.click_settings.click_distance = try_opt!(value.parse().ok()),
+            "long_press_time" => {
+                state.click_settings.long_press_time = try_opt!(value.parse().ok())
+            },
+            _ => return None,
+        }
+    }
+
+    Some(state)
+}
+
+/// Parses the input of a `frame` line, without the time.
+fn parse_input(words: &[&str]) -> Option<Input> {
+    let vec = |words: &[&str]| -> Option<Vec2<u32>> {
+        if words.len() != 2 {
+            return None;
+        }
+
+        match (words[0].parse(), words[1].parse()) {
+            (Ok(x), Ok(y)) => Some(Vec2::new(x, y)),
+            _ => None,
+        }
+    };
+
+    let vecf = |words: &[&str]| -> Option<Vec2<f32>> {
+        if words.len() != 2 {
+            return None;
+        }
+
+        match (words[0].parse(), words[1].parse()) {
+            (Ok(x), Ok(y)) => Some(Vec2::new(x, y)),
+            _ => None,
+        }
+    };
+
+    let rest = &words[1 ..];
+
+    match words[0] {
+        "start" if rest.len() >= 2 => {
+            let viewport = try_opt!(vec(&rest[.. 2]));
+            parse_initial_state(viewport, &rest[2 ..]).map(Input::Start)
+        },
+        "viewport" => vec(rest).map(Input::SetViewport),
+        "dpi_factor" if rest.len() == 1 => rest[0].parse().ok().map(Input::SetDpiFactor),
+        "unit" if rest.len() == 1 => parse_unit(rest[0]).map(Input::SetUnit),
+        "origin" if rest.len() == 1 => parse_origin(rest[0]).map(Input::SetOrigin),
+        "mouse_position" if rest == ["none"] => Some(Input::SetMousePosition(None)),
+        "mouse_position" => vec(rest).map(|v| Input::SetMousePosition(Some(v))),
+        "mouse_pressed" if rest.len() == 1 => rest[0].parse().ok().map(Input::SetMousePressed),
+        "mouse_button_pressed" if rest.len() == 2 => {
+            let button = try_opt!(parse_button(rest[0]));
+            rest[1].parse().ok().map(|pressed| Input::SetMouseButtonPressed(button, pressed))
+        },
+        "pointer_position" if rest.len() >= 2 => {
+            let pointer = try_opt!(parse_pointer(rest[0]));
+            if rest[1 ..] == ["none"] {
+                Some(Input::SetPointerPosition(pointer, None))
+            } else {
+                vec(&rest[1 ..]).map(|v| Input::SetPointerPosition(pointer, Some(v)))
+            }
+        },
+        "pointer_button_pressed" if rest.len() == 3 => {
+            let pointer = try_opt!(parse_pointer(rest[0]));
+            let button = try_opt!(parse_button(rest[1]));
+            rest[2].parse().ok().map(|pressed| {
+                Input::SetPointerButtonPressed(pointer, button, pressed)
+            })
+        },
+        "remove_pointer" if rest.len() == 1 => parse_pointer(rest[0]).map(Input::RemovePointer),
+        "tick" if rest.len() == 1 => rest[0].parse().ok().map(Input::Tick),
+        "scroll" if rest.len() == 3 => {
+            let delta = vecf(&rest[1 ..]);
+            match rest[0] {
+                "lines" => delta.map(|d| Input::Scroll(ScrollDelta::Lines(d))),
+                "pixels" => delta.map(|d| Input::Scroll(ScrollDelta::Pixels(d))),
+                "logical" => delta.map(|d| Input::Scroll(ScrollDelta::Logical(d))),
+                _ => None,
+            }
+        },
+        "character" if rest.len() == 1 => {
+            rest[0].parse().ok().and_then(::std::char::from_u32).map(Input::SendCharacter)
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vec2;
+
+    use Component;
+    use Layout;
+    use PositionnedChild;
+    use component::MouseButton;
+    use predefined::ButtonComponent;
+    use predefined::button::ButtonEvent;
+    use ui::{ClickSettings, Origin, Ui, Unit};
+    use super::{Input, Recorder, Recording};
+
+    /// A button of 100 by 30 logical pixels in the corner of the viewport.
+    #[derive(Default)]
+    struct Panel {
+        button: ButtonComponent,
+    }
+
+    impl Component for Panel {
+        type EmittedEvent = ButtonEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::PositionnedChildren(vec![PositionnedChild::new(&mut self.button, 0.0, 0.0)],
+                                        None)
+        }
+
+        fn handle_child_event(&mut self, _: usize, event: ButtonEvent) -> Option<ButtonEvent> {
+            Some(event)
+        }
+    }
+
+    fn ui() -> Ui<Panel, ButtonEvent> {
+        Ui::new(Default::default(), Vec2::new(800, 600))
+    }
+
+    /// Clicks on a pixel that is only over the button if the DPI factor is 2 and the origin is
+    /// the top-left hand corner.
+    fn record(ui: Ui<Panel, ButtonEvent>) -> Recording {
+        let mut recorder = Recorder::new(ui);
+        recorder.set_mouse_position(Some(Vec2::new(150, 40)));
+        recorder.set_mouse_pressed(true);
+        recorder.tick(0.1);
+        recorder.set_mouse_button_pressed(MouseButton::Left, false);
+        recorder.set_mouse_position(None);
+        recorder.into_recording()
+    }
+
+    /// Returns the recording without the times, which are rounded when saved.
+    fn without_times(mut recording: Recording) -> Recording {
+        for frame in recording.frames.iter_mut() {
+            frame.time = 0.0;
+        }
+
+        recording
+    }
+
+    #[test]
+    fn replay_with_initial_settings() {
+        let mut recorded = ui();
+        recorded.set_dpi_factor(2.0);
+        recorded.set_origin(Origin::TopLeft);
+        recorded.set_click_settings(ClickSettings { click_distance: 10.0, ..Default::default() });
+
+        let recording = record(recorded);
+        assert_eq!(recording.frames[4].events, vec!["Triggered".to_string()]);
+
+        // the fresh `Ui` has the default settings, which the recording must restore
+        let recording = Recording::parse(&recording.to_string()).unwrap();
+        assert_eq!(recording.replay(&mut ui()), Ok(()));
+    }
+
+    #[test]
+    fn replay_detects_divergence() {
+        let recording = record(ui());
+
+        let mut other = ui();
+        other.get_mut_main_component().button.set_color([0.0, 0.0, 1.0]);
+        let mismatch = recording.replay(&mut other).unwrap_err();
+        assert_eq!(mismatch.frame, 0);
+        assert!(mismatch.description.contains("changed color"));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let recording = record(ui());
+        let parsed = Recording::parse(&recording.to_string()).unwrap();
+        assert_eq!(without_times(parsed), without_times(recording));
+    }
+
+    #[test]
+    fn replay_unit_and_origin_changes() {
+        let mut recorder = Recorder::new(ui());
+        recorder.set_unit(Unit::PhysicalPixels);
+        recorder.set_origin(Origin::TopLeft);
+        recorder.set_mouse_position(Some(Vec2::new(50, 10)));
+        recorder.set_mouse_pressed(true);
+        recorder.set_mouse_button_pressed(MouseButton::Left, false);
+
+        let recording = recorder.into_recording();
+        assert_eq!(recording.frames[5].events, vec!["Triggered".to_string()]);
+
+        let parsed = Recording::parse(&recording.to_string()).unwrap();
+        assert_eq!(parsed.frames[1].input, Input::SetUnit(Unit::PhysicalPixels));
+        assert_eq!(parsed.frames[2].input, Input::SetOrigin(Origin::TopLeft));
+        assert_eq!(parsed.replay(&mut ui()), Ok(()));
+    }
+
+    #[test]
+    fn parse_start() {
+        let recording = Recording::parse("frame 0.000000 start 800 600 dpi_factor=1.5 \
+                                          unit=normalized origin=top_left \
+                                          long_press_time=2\n").unwrap();
+        match recording.frames[0].input {
+            Input::Start(ref state) => {
+                assert_eq!(state.viewport, Vec2::new(800, 600));
+                assert_eq!(state.dpi_factor, 1.5);
+                assert_eq!(state.unit, Unit::Normalized);
+                assert_eq!(state.origin, Origin::TopLeft);
+                assert_eq!(state.click_settings.long_press_time, 2.0);
+                assert_eq!(state.click_settings.click_distance, 4.0);
+            },
+            _ => panic!(),
+        }
+
+        assert!(Recording::parse("frame 0.000000 start 800 600 unit=meters\n").is_err());
+    }
+}
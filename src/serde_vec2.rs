@@ -0,0 +1,21 @@
+//! Serialization of `Vec2<f32>` with serde, for use with `#[serde(with = "ui::serde_vec2")]`.
+//!
+//! Vectors are represented as a `(x, y)` tuple.
+
+use nalgebra::Vec2;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes a vector as a `(x, y)` tuple.
+pub fn serialize<S>(vec: &Vec2<f32>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    (vec.x, vec.y).serialize(serializer)
+}
+
+/// Deserializes a vector from a `(x, y)` tuple.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec2<f32>, D::Error>
+    where D: Deserializer<'de>
+{
+    let (x, y) = try!(<(f32, f32)>::deserialize(deserializer));
+    Ok(Vec2::new(x, y))
+}
@@ -1,15 +1,24 @@
 use std::default::Default;
+use std::fmt;
 use nalgebra::{Vec2};
 
+use transform::Transform;
+
 /// A shape that can be drawn on the screen.
 ///
-/// The positions and sizes are in logical coords. `(-1.0, -1.0)` corresponds to the bottom-left
-/// hand corner of the screen, and `(1.0, 1.0)` corresponds to the top-right hand corner.
-#[derive(Debug)]
+/// The shapes produced by the components are relative to the main component and expressed in
+/// the unit chosen with `Ui::set_unit`.
+///
+/// The shapes returned by `Ui::draw` are in normalized coords instead. `(-1.0, -1.0)`
+/// corresponds to the bottom-left hand corner of the screen, and `(1.0, 1.0)` corresponds to the
+/// top-right hand corner.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Shape {
     /// A single point.
     Point {
         /// Position of the center of the point.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         location: Vec2<f32>,
         /// Color of the point.
         color: [f32; 3],
@@ -18,8 +27,10 @@ pub enum Shape {
     /// A single line.
     Line {
         /// Position of one extremity of the line.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         from: Vec2<f32>,
         /// Position of the other extremity of the line.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         to: Vec2<f32>,
         /// Color of the line.
         color: [f32; 3],
@@ -28,8 +39,10 @@ pub enum Shape {
     /// A rectangle. Can only be perpendicular to the screen.
     Rectangle {
         /// Position of one edge of the rectangle.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         from: Vec2<f32>,
         /// Position of the other edge of the rectangle.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         to: Vec2<f32>,
         /// Color of the rectangle.
         color: [f32; 3],
@@ -38,8 +51,10 @@ pub enum Shape {
     /// An image. Can only be perpendicular to the screen.
     Image {
         /// Position of one edge of the rectangle.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         from: Vec2<f32>,
         /// Position of the other edge of the rectangle.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         to: Vec2<f32>,
         /// Image to draw.
         image: Image,
@@ -52,14 +67,42 @@ pub enum Shape {
         /// Font to use.
         font: Font,
         /// Position of the bottom-left hand corner of the first letter of the text.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
         bottom_left: Vec2<f32>,
         /// Size of one EM of text.
         em: f32,
     },
+
+    /// Another shape with a transformation applied to it. Used for transformations that the
+    /// other shapes can't represent, such as rotated rectangles.
+    Transformed {
+        /// The transformation to apply.
+        transform: Transform,
+        /// The shape to transform.
+        shape: Box<Shape>,
+    },
+
+    /// Another shape of which only the part inside a rectangle is drawn. The rectangle can only
+    /// be perpendicular to the screen.
+    ///
+    /// Renderers usually implement this with a scissor. When clipped shapes are nested, for
+    /// example inside a `Transformed`, only the intersection of the rectangles is drawn.
+    /// `apply_clip` computes the result on the CPU instead, for backends without scissors.
+    Clipped {
+        /// Position of one edge of the rectangle.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
+        from: Vec2<f32>,
+        /// Position of the other edge of the rectangle.
+        #[cfg_attr(feature = "serde", serde(with = "::serde_vec2"))]
+        to: Vec2<f32>,
+        /// The shape to clip.
+        shape: Box<Shape>,
+    },
 }
 
 /// All the possible images.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Image {
     /// The image corresponding to a regular button.
     UnhoveredButton,
@@ -73,6 +116,7 @@ pub enum Image {
 
 /// All the possible fonts.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Font {
     /// The default font for regular text.
     Default,
@@ -90,34 +134,953 @@ impl Default for Font {
     }
 }
 
+/// Provides the dimensions of the characters of the fonts, in order to compute the size of
+/// texts.
+pub trait FontMetrics {
+    /// Returns the horizontal distance between the start of a character and the start of the
+    /// next one, in EMs.
+    fn get_advance(&self, font: &Font, character: char) -> f32;
+
+    /// Returns the distance between the bottom of the text and the top of its tallest
+    /// characters, in EMs.
+    fn get_ascent(&self, &Font) -> f32 {
+        1.0
+    }
+
+    /// Returns the distance by which characters such as `g` go below the bottom of the text, in
+    /// EMs.
+    fn get_descent(&self, &Font) -> f32 {
+        0.0
+    }
+}
+
+/// Metrics that consider every character to be one EM wide and one EM tall. Used when no
+/// better metrics are available.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonospaceMetrics;
+
+impl FontMetrics for MonospaceMetrics {
+    fn get_advance(&self, _: &Font, _: char) -> f32 {
+        1.0
+    }
+}
+
+/// Maximum distance at which a position is considered to touch a point or a line.
+const TOLERANCE: f32 = 1e-5;
+
 impl Shape {
     /// Moves a shape by the given coordinates.
-    pub fn translate(mut self, vec: Vec2<f32>) -> Shape {
-        match &mut self {
-            &mut Shape::Point { ref mut location, .. } => {
-                *location = location.clone() + vec;
+    pub fn translate(self, vec: Vec2<f32>) -> Shape {
+        match self {
+            Shape::Point { location, color } => {
+                Shape::Point { location: location + vec, color: color }
+            },
+
+            Shape::Line { from, to, color } => {
+                Shape::Line { from: from + vec, to: to + vec, color: color }
             },
 
-            &mut Shape::Line { ref mut from, ref mut to, .. } => {
-                *from = from.clone() + vec;
-                *to = to.clone() + vec;
+            Shape::Rectangle { from, to, color } => {
+                Shape::Rectangle { from: from + vec, to: to + vec, color: color }
             },
 
-            &mut Shape::Rectangle { ref mut from, ref mut to, .. } => {
-                *from = from.clone() + vec;
-                *to = to.clone() + vec;
+            Shape::Image { from, to, image } => {
+                Shape::Image { from: from + vec, to: to + vec, image: image }
             },
 
-            &mut Shape::Image { ref mut from, ref mut to, .. } => {
-                *from = from.clone() + vec;
-                *to = to.clone() + vec;
+            Shape::Text { text, font, bottom_left, em } => {
+                Shape::Text { text: text, font: font, bottom_left: bottom_left + vec, em: em }
             },
 
-            &mut Shape::Text { ref mut bottom_left, .. } => {
-                *bottom_left = bottom_left.clone() + vec;
+            Shape::Transformed { transform, shape } => {
+                Shape::Transformed {
+                    transform: transform.then(&Transform::translation(vec)),
+                    shape: shape,
+                }
+            },
+
+            Shape::Clipped { from, to, shape } => {
+                Shape::Clipped {
+                    from: from + vec,
+                    to: to + vec,
+                    shape: Box::new(shape.translate(vec)),
+                }
             },
         }
+    }
+
+    /// Multiplies the coordinates of a shape by the given factors.
+    pub fn scale(self, factors: Vec2<f32>) -> Shape {
+        self.transform(&Transform::scale(factors))
+    }
+
+    /// Applies a transformation to a shape.
+    ///
+    /// Shapes that can't represent the result, for example rectangles that get rotated, are
+    /// wrapped inside a `Shape::Transformed`.
+    pub fn transform(self, transform: &Transform) -> Shape {
+        let aligned = transform.is_axis_aligned();
+        let uniform = aligned && transform.matrix[0][0] > 0.0 &&
+                      transform.matrix[0][0] == transform.matrix[1][1];
+
+        match self {
+            Shape::Point { location, color } => {
+                Shape::Point { location: transform.apply(location), color: color }
+            },
+
+            Shape::Line { from, to, color } => {
+                Shape::Line { from: transform.apply(from), to: transform.apply(to), color: color }
+            },
+
+            Shape::Rectangle { from, to, color } if aligned => {
+                Shape::Rectangle {
+                    from: transform.apply(from),
+                    to: transform.apply(to),
+                    color: color,
+                }
+            },
+
+            Shape::Image { from, to, image } if aligned => {
+                Shape::Image {
+                    from: transform.apply(from),
+                    to: transform.apply(to),
+                    image: image,
+                }
+            },
+
+            Shape::Text { text, font, bottom_left, em } if uniform => {
+                Shape::Text {
+                    text: text,
+                    font: font,
+                    bottom_left: transform.apply(bottom_left),
+                    em: em * transform.matrix[0][0],
+                }
+            },
+
+            Shape::Transformed { transform: inner, shape } => {
+                Shape::Transformed { transform: inner.then(transform), shape: shape }
+            },
+
+            Shape::Clipped { from, to, shape } if aligned => {
+                Shape::Clipped {
+                    from: transform.apply(from),
+                    to: transform.apply(to),
+                    shape: Box::new(shape.transform(transform)),
+                }
+            },
+
+            shape => {
+                Shape::Transformed { transform: *transform, shape: Box::new(shape) }
+            },
+        }
+    }
+
+    /// Mirrors the positions of a shape so that `y` becomes `height - y`.
+    ///
+    /// Contrary to `transform`, texts and images stay upright: their box is mirrored instead of
+    /// their content. The `bottom_left` of a text is therefore treated as its top-left hand
+    /// corner.
+    pub fn flip_vertically(self, height: f32) -> Shape {
+        let flip = |position: Vec2<f32>| Vec2::new(position.x, height - position.y);
+
+        match self {
+            Shape::Point { location, color } => {
+                Shape::Point { location: flip(location), color: color }
+            },
+
+            Shape::Line { from, to, color } => {
+                Shape::Line { from: flip(from), to: flip(to), color: color }
+            },
+
+            Shape::Rectangle { from, to, color } => {
+                Shape::Rectangle { from: flip(from), to: flip(to), color: color }
+            },
+
+            Shape::Image { from, to, image } => {
+                Shape::Image {
+                    from: Vec2::new(from.x, height - to.y),
+                    to: Vec2::new(to.x, height - from.y),
+                    image: image,
+                }
+            },
+
+            Shape::Text { text, font, bottom_left, em } => {
+                Shape::Text {
+                    text: text,
+                    font: font,
+                    bottom_left: Vec2::new(bottom_left.x, height - bottom_left.y - em),
+                    em: em,
+                }
+            },
+
+            Shape::Transformed { transform, shape } => {
+                let mirror = Transform::scale(Vec2::new(1.0, -1.0))
+                                 .then(&Transform::translation(Vec2::new(0.0, height)));
+
+                Shape::Transformed {
+                    transform: mirror.then(&transform).then(&mirror),
+                    shape: Box::new(shape.flip_vertically(height)),
+                }
+            },
+
+            Shape::Clipped { from, to, shape } => {
+                Shape::Clipped {
+                    from: flip(from),
+                    to: flip(to),
+                    shape: Box::new(shape.flip_vertically(height)),
+                }
+            },
+        }
+    }
+
+    /// Restricts the drawing of the shape to the rectangle between two positions.
+    ///
+    /// Clipping a shape that is already clipped keeps the intersection of the two rectangles.
+    pub fn clip(self, from: Vec2<f32>, to: Vec2<f32>) -> Shape {
+        match self {
+            Shape::Clipped { from: inner_from, to: inner_to, shape } => {
+                let (min, max) = intersect((from, to), (inner_from, inner_to));
+                Shape::Clipped { from: min, to: max, shape: shape }
+            },
+
+            shape => Shape::Clipped { from: from, to: to, shape: Box::new(shape) },
+        }
+    }
+
+    /// Computes the clipping of the shape on the CPU as far as possible. Returns `None` if
+    /// nothing of the shape is visible.
+    ///
+    /// Rectangles and lines are cut to their clip rectangle, points outside of it are removed,
+    /// and shapes entirely inside of it are no longer clipped. The other shapes, such as texts
+    /// that cross the border of their clip rectangle, stay inside a `Shape::Clipped` and the
+    /// renderers must only draw their pixels inside of it, for example with a scissor.
+    pub fn apply_clip(self) -> Option<Shape> {
+        match self {
+            Shape::Clipped { from, to, shape } => {
+                let min = Vec2::new(from.x.min(to.x), from.y.min(to.y));
+                let max = Vec2::new(from.x.max(to.x), from.y.max(to.y));
+
+                let shape = match shape.apply_clip() {
+                    Some(shape) => shape,
+                    None => return None,
+                };
+
+                match shape {
+                    Shape::Point { location, color } => {
+                        if location.x >= min.x && location.x <= max.x &&
+                           location.y >= min.y && location.y <= max.y
+                        {
+                            Some(Shape::Point { location: location, color: color })
+                        } else {
+                            None
+                        }
+                    },
+
+                    Shape::Line { from, to, color } => {
+                        clip_line(from, to, min, max).map(|(from, to)| {
+                            Shape::Line { from: from, to: to, color: color }
+                        })
+                    },
+
+                    Shape::Rectangle { from, to, color } => {
+                        let (from, to) = intersect((from, to), (min, max));
+                        if from.x < to.x && from.y < to.y {
+                            Some(Shape::Rectangle { from: from, to: to, color: color })
+                        } else {
+                            None
+                        }
+                    },
+
+                    shape => {
+                        let (bottom_left, top_right) = shape.bounding_box();
+
+                        if bottom_left.x >= min.x && top_right.x <= max.x &&
+                           bottom_left.y >= min.y && top_right.y <= max.y
+                        {
+                            Some(shape)
+                        } else if bottom_left.x >= max.x || top_right.x <= min.x ||
+                                  bottom_left.y >= max.y || top_right.y <= min.y
+                        {
+                            None
+                        } else {
+                            Some(Shape::Clipped { from: min, to: max, shape: Box::new(shape) })
+                        }
+                    },
+                }
+            },
+
+            Shape::Transformed { transform, shape } => {
+                shape.apply_clip().map(|shape| {
+                    Shape::Transformed { transform: transform, shape: Box::new(shape) }
+                })
+            },
+
+            shape => Some(shape),
+        }
+    }
+
+    /// Returns the bottom-left and top-right corners of the smallest rectangle that contains
+    /// the shape.
+    ///
+    /// The size of texts is computed with `MonospaceMetrics`.
+    pub fn bounding_box(&self) -> (Vec2<f32>, Vec2<f32>) {
+        self.bounding_box_with_metrics(&MonospaceMetrics)
+    }
+
+    /// Same as `bounding_box`, but computes the size of texts with the given metrics.
+    pub fn bounding_box_with_metrics(&self, metrics: &FontMetrics) -> (Vec2<f32>, Vec2<f32>) {
+        match self {
+            &Shape::Point { location, .. } => (location, location),
+
+            &Shape::Line { from, to, .. } |
+            &Shape::Rectangle { from, to, .. } |
+            &Shape::Image { from, to, .. } => {
+                (Vec2::new(from.x.min(to.x), from.y.min(to.y)),
+                 Vec2::new(from.x.max(to.x), from.y.max(to.y)))
+            },
+
+            &Shape::Text { ref text, ref font, bottom_left, em } => {
+                let width = text.chars().map(|c| metrics.get_advance(font, c))
+                                .fold(0.0, |a, b| a + b);
+
+                (Vec2::new(bottom_left.x, bottom_left.y - metrics.get_descent(font) * em),
+                 Vec2::new(bottom_left.x + width * em,
+                           bottom_left.y + metrics.get_ascent(font) * em))
+            },
+
+            &Shape::Transformed { ref transform, ref shape } => {
+                let (bottom_left, top_right) = shape.bounding_box_with_metrics(metrics);
+
+                let corners = [
+                    transform.apply(bottom_left),
+                    transform.apply(Vec2::new(top_right.x, bottom_left.y)),
+                    transform.apply(top_right),
+                    transform.apply(Vec2::new(bottom_left.x, top_right.y)),
+                ];
+
+                let mut min = corners[0];
+                let mut max = corners[0];
+                for corner in corners.iter() {
+                    min = Vec2::new(min.x.min(corner.x), min.y.min(corner.y));
+                    max = Vec2::new(max.x.max(corner.x), max.y.max(corner.y));
+                }
+
+                (min, max)
+            },
+
+            &Shape::Clipped { from, to, ref shape } => {
+                intersect(shape.bounding_box_with_metrics(metrics), (from, to))
+            },
+        }
+    }
+
+    /// Returns true if the given position touches the shape.
+    ///
+    /// The size of texts is computed with `MonospaceMetrics`.
+    pub fn contains(&self, position: Vec2<f32>) -> bool {
+        self.contains_with_metrics(position, &MonospaceMetrics)
+    }
+
+    /// Same as `contains`, but computes the size of texts with the given metrics.
+    pub fn contains_with_metrics(&self, position: Vec2<f32>, metrics: &FontMetrics) -> bool {
+        match self {
+            &Shape::Point { location, .. } => {
+                (position.x - location.x).abs() <= TOLERANCE &&
+                (position.y - location.y).abs() <= TOLERANCE
+            },
+
+            &Shape::Line { from, to, .. } => {
+                let (dx, dy) = (to.x - from.x, to.y - from.y);
+                let length_sq = dx * dx + dy * dy;
+
+                // projecting the position on the line, then clamping to the extremities
+                let t = if length_sq == 0.0 {
+                    0.0
+                } else {
+                    (((position.x - from.x) * dx + (position.y - from.y) * dy) / length_sq)
+                        .max(0.0).min(1.0)
+                };
+
+                let (px, py) = (from.x + t * dx - position.x, from.y + t * dy - position.y);
+                (px * px + py * py).sqrt() <= TOLERANCE
+            },
+
+            &Shape::Rectangle { .. } | &Shape::Image { .. } | &Shape::Text { .. } => {
+                let (bottom_left, top_right) = self.bounding_box_with_metrics(metrics);
+                position.x >= bottom_left.x && position.x < top_right.x &&
+                position.y >= bottom_left.y && position.y < top_right.y
+            },
+
+            &Shape::Transformed { ref transform, ref shape } => {
+                match transform.inverse() {
+                    Some(inverse) => shape.contains_with_metrics(inverse.apply(position), metrics),
+                    None => false,
+                }
+            },
+
+            &Shape::Clipped { from, to, ref shape } => {
+                position.x >= from.x.min(to.x) && position.x < from.x.max(to.x) &&
+                position.y >= from.y.min(to.y) && position.y < from.y.max(to.y) &&
+                shape.contains_with_metrics(position, metrics)
+            },
+        }
+    }
+
+    /// Returns the bottom-left and top-right corners of the smallest rectangle that contains
+    /// all the shapes, or `None` if the list is empty.
+    pub fn bounding_box_of(shapes: &[Shape]) -> Option<(Vec2<f32>, Vec2<f32>)> {
+        shapes.iter().map(|s| s.bounding_box()).fold(None, |acc, (bottom_left, top_right)| {
+            match acc {
+                None => Some((bottom_left, top_right)),
+                Some((acc_bl, acc_tr)) => Some((
+                    Vec2::new(acc_bl.x.min(bottom_left.x), acc_bl.y.min(bottom_left.y)),
+                    Vec2::new(acc_tr.x.max(top_right.x), acc_tr.y.max(top_right.y)),
+                )),
+            }
+        })
+    }
+}
+
+/// Returns the bottom-left and top-right corners of the intersection of two rectangles given
+/// by two of their opposite corners.
+///
+/// If the rectangles don't overlap, the result is an empty rectangle on the border of the
+/// second one.
+fn intersect(a: (Vec2<f32>, Vec2<f32>), b: (Vec2<f32>, Vec2<f32>)) -> (Vec2<f32>, Vec2<f32>) {
+    let b_min = Vec2::new(b.0.x.min(b.1.x), b.0.y.min(b.1.y));
+    let b_max = Vec2::new(b.0.x.max(b.1.x), b.0.y.max(b.1.y));
+
+    let min = Vec2::new(a.0.x.min(a.1.x).max(b_min.x).min(b_max.x),
+                        a.0.y.min(a.1.y).max(b_min.y).min(b_max.y));
+    let max = Vec2::new(a.0.x.max(a.1.x).min(b_max.x).max(min.x),
+                        a.0.y.max(a.1.y).min(b_max.y).max(min.y));
+
+    (min, max)
+}
+
+/// Returns the part of the line between `from` and `to` that is inside the rectangle between
+/// `min` and `max`, or `None` if the line is entirely outside.
+fn clip_line(from: Vec2<f32>, to: Vec2<f32>, min: Vec2<f32>, max: Vec2<f32>)
+             -> Option<(Vec2<f32>, Vec2<f32>)>
+{
+    let delta = to - from;
+    let mut start = 0.0f32;
+    let mut end = 1.0f32;
+
+    // Liang-Barsky: each side of the rectangle restricts the range of the parameter
+    let sides = [(-delta.x, from.x - min.x), (delta.x, max.x - from.x),
+                 (-delta.y, from.y - min.y), (delta.y, max.y - from.y)];
+
+    for &(p, q) in sides.iter() {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            start = start.max(q / p);
+        } else {
+            end = end.min(q / p);
+        }
+    }
+
+    if start > end {
+        return None;
+    }
+
+    Some((Vec2::new(from.x + delta.x * start, from.y + delta.y * start),
+          Vec2::new(from.x + delta.x * end, from.y + delta.y * end)))
+}
+
+/// Writes the shape in the canonical format used by snapshots.
+///
+/// The format holds on one line and rounds floats to three decimals, so that it stays stable
+/// in the face of small rounding differences.
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Shape::Point { ref location, ref color } => {
+                write!(f, "point location={} color={}", Canonical(location), Canonical(color))
+            },
+
+            &Shape::Line { ref from, ref to, ref color } => {
+                write!(f, "line from={} to={} color={}", Canonical(from), Canonical(to),
+                       Canonical(color))
+            },
+
+            &Shape::Rectangle { ref from, ref to, ref color } => {
+                write!(f, "rectangle from={} to={} color={}", Canonical(from), Canonical(to),
+                       Canonical(color))
+            },
+
+            &Shape::Image { ref from, ref to, ref image } => {
+                write!(f, "image from={} to={} image={}", Canonical(from), Canonical(to), image)
+            },
+
+            &Shape::Text { ref text, ref font, ref bottom_left, em } => {
+                write!(f, "text font={} bottom_left={} em={} text={:?}", font,
+                       Canonical(bottom_left), Canonical(&em), text)
+            },
+
+            &Shape::Transformed { ref transform, ref shape } => {
+                let m = &transform.matrix;
+                write!(f, "transformed matrix=({},{},{},{},{},{}) shape={}", Canonical(&m[0][0]),
+                       Canonical(&m[0][1]), Canonical(&m[0][2]), Canonical(&m[1][0]),
+                       Canonical(&m[1][1]), Canonical(&m[1][2]), shape)
+            },
+
+            &Shape::Clipped { ref from, ref to, ref shape } => {
+                write!(f, "clipped from={} to={} shape={}", Canonical(from), Canonical(to), shape)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Image::UnhoveredButton => write!(f, "unhovered_button"),
+            &Image::HoveredButton => write!(f, "hovered_button"),
+            &Image::Custom(ref name) => write!(f, "custom({:?})", name),
+        }
+    }
+}
+
+impl fmt::Display for Font {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Font::Default => write!(f, "default"),
+            &Font::Button => write!(f, "button"),
+            &Font::Custom(ref name) => write!(f, "custom({:?})", name),
+        }
+    }
+}
+
+/// Wrapper that writes numbers in the canonical format.
+struct Canonical<'a, T: 'a>(&'a T);
+
+impl<'a> fmt::Display for Canonical<'a, f32> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = format!("{:.3}", self.0);
+        if value == "-0.000" {
+            write!(f, "0.000")
+        } else {
+            write!(f, "{}", value)
+        }
+    }
+}
+
+impl<'a> fmt::Display for Canonical<'a, Vec2<f32>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({},{})", Canonical(&self.0.x), Canonical(&self.0.y))
+    }
+}
+
+impl<'a> fmt::Display for Canonical<'a, [f32; 3]> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({},{},{})", Canonical(&self.0[0]), Canonical(&self.0[1]),
+               Canonical(&self.0[2]))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use nalgebra::Vec2;
+
+    use transform::Transform;
+    use super::{Font, FontMetrics, Image, Shape};
+
+    fn v(x: f32, y: f32) -> Vec2<f32> {
+        Vec2::new(x, y)
+    }
+
+    fn assert_close(a: (Vec2<f32>, Vec2<f32>), b: (Vec2<f32>, Vec2<f32>)) {
+        let close = |a: Vec2<f32>, b: Vec2<f32>| {
+            (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+        };
+        if !close(a.0, b.0) || !close(a.1, b.1) {
+            panic!("{:?} is not close to {:?}", a, b);
+        }
+    }
+
+    fn rectangle(from: Vec2<f32>, to: Vec2<f32>) -> Shape {
+        Shape::Rectangle { from: from, to: to, color: [1.0, 1.0, 1.0] }
+    }
+
+    fn text(text: &str, bottom_left: Vec2<f32>, em: f32) -> Shape {
+        Shape::Text { text: text.to_string(), font: Font::Default, bottom_left: bottom_left,
+                      em: em }
+    }
+
+    /// Metrics where `i` is narrow, and where characters go below the bottom of the text.
+    struct NarrowMetrics;
+
+    impl FontMetrics for NarrowMetrics {
+        fn get_advance(&self, _: &Font, character: char) -> f32 {
+            if character == 'i' { 0.25 } else { 0.5 }
+        }
+
+        fn get_ascent(&self, _: &Font) -> f32 {
+            0.75
+        }
+
+        fn get_descent(&self, _: &Font) -> f32 {
+            0.25
+        }
+    }
+
+    #[test]
+    fn point() {
+        let point = Shape::Point { location: v(1.0, 2.0), color: [1.0, 0.0, 0.0] };
+        assert_eq!(point.bounding_box(), (v(1.0, 2.0), v(1.0, 2.0)));
+        assert!(point.contains(v(1.0, 2.0)));
+        assert!(!point.contains(v(1.0, 2.1)));
+
+        let moved = point.clone().transform(&Transform::rotation(PI / 2.0));
+        match moved {
+            Shape::Point { location, .. } => assert_close((location, location),
+                                                          (v(-2.0, 1.0), v(-2.0, 1.0))),
+            _ => panic!("{:?}", moved),
+        }
+
+        assert_eq!(point.translate(v(1.0, 1.0)),
+                   Shape::Point { location: v(2.0, 3.0), color: [1.0, 0.0, 0.0] });
+    }
+
+    #[test]
+    fn line() {
+        let line = Shape::Line { from: v(2.0, 0.0), to: v(0.0, 2.0), color: [1.0; 3] };
+        assert_eq!(line.bounding_box(), (v(0.0, 0.0), v(2.0, 2.0)));
+        assert!(line.contains(v(1.0, 1.0)));
+        assert!(line.contains(v(2.0, 0.0)));
+        assert!(!line.contains(v(0.5, 0.5)));
+        assert!(!line.contains(v(3.0, -1.0)));
+
+        let degenerate = Shape::Line { from: v(1.0, 1.0), to: v(1.0, 1.0), color: [1.0; 3] };
+        assert!(degenerate.contains(v(1.0, 1.0)));
+
+        // lines stay lines even when rotated
+        let rotated = line.transform(&Transform::rotation(PI / 4.0));
+        match rotated {
+            Shape::Line { .. } => (),
+            _ => panic!("{:?}", rotated),
+        }
+    }
+
+    #[test]
+    fn rectangle_shape() {
+        // corners given in any order
+        let shape = rectangle(v(3.0, 4.0), v(1.0, 2.0));
+        assert_eq!(shape.bounding_box(), (v(1.0, 2.0), v(3.0, 4.0)));
+        assert!(shape.contains(v(1.0, 2.0)));
+        assert!(shape.contains(v(2.0, 3.0)));
+        assert!(!shape.contains(v(3.0, 3.0)));
+        assert!(!shape.contains(v(0.0, 3.0)));
+
+        let scaled = shape.clone().scale(v(2.0, -1.0));
+        assert_eq!(scaled, rectangle(v(6.0, -4.0), v(2.0, -2.0)));
+        assert_eq!(scaled.bounding_box(), (v(2.0, -4.0), v(6.0, -2.0)));
+
+        let translated = shape.clone().transform(&Transform::translation(v(1.0, 1.0)));
+        assert_eq!(translated, shape.clone().translate(v(1.0, 1.0)));
+        assert_eq!(translated, rectangle(v(4.0, 5.0), v(2.0, 3.0)));
+    }
+
+    #[test]
+    fn rotated_rectangle() {
+        let shape = rectangle(v(0.0, 0.0), v(2.0, 1.0));
+        let rotated = shape.clone().transform(&Transform::rotation(PI / 2.0));
+
+        match rotated {
+            Shape::Transformed { ref shape, .. } => {
+                assert_eq!(**shape, rectangle(v(0.0, 0.0), v(2.0, 1.0)))
+            },
+            _ => panic!("{:?}", rotated),
+        }
+
+        assert_close(rotated.bounding_box(), (v(-1.0, 0.0), v(0.0, 2.0)));
+        assert!(rotated.contains(v(-0.5, 1.5)));
+        assert!(!rotated.contains(v(0.5, 0.5)));
+        assert!(!rotated.contains(v(-0.5, 2.5)));
+
+        // a rotation by 45 degrees covers a larger box than the rectangle
+        let diagonal = rectangle(v(-1.0, -1.0), v(1.0, 1.0))
+                           .transform(&Transform::rotation(PI / 4.0));
+        let half_diagonal = 2.0f32.sqrt();
+        assert_close(diagonal.bounding_box(),
+                     (v(-half_diagonal, -half_diagonal), v(half_diagonal, half_diagonal)));
+        assert!(diagonal.contains(v(0.0, 1.3)));
+        assert!(!diagonal.contains(v(0.95, 0.95)));
+    }
+
+    #[test]
+    fn transformed_stays_transformed() {
+        let rotation = Transform::rotation(PI / 2.0);
+        let shape = rectangle(v(0.0, 0.0), v(2.0, 1.0)).transform(&rotation)
+                        .translate(v(10.0, 0.0)).transform(&Transform::scale(v(2.0, 2.0)));
+
+        match shape {
+            Shape::Transformed { ref shape, .. } => match **shape {
+                Shape::Rectangle { .. } => (),
+                _ => panic!("{:?}", shape),
+            },
+            _ => panic!("{:?}", shape),
+        }
+
+        assert_close(shape.bounding_box(), (v(18.0, 0.0), v(20.0, 4.0)));
+        assert!(shape.contains(v(19.0, 3.0)));
+
+        // transformations that can't be inverted have no area
+        let flat = rectangle(v(0.0, 0.0), v(1.0, 1.0))
+                       .transform(&Transform { matrix: [[1.0, 1.0, 0.0], [1.0, 1.0, 0.0]] });
+        assert!(!flat.contains(v(0.5, 0.5)));
+    }
+
+    #[test]
+    fn image() {
+        let image = Shape::Image { from: v(0.0, 0.0), to: v(1.0, 2.0),
+                                   image: Image::HoveredButton };
+        assert_eq!(image.bounding_box(), (v(0.0, 0.0), v(1.0, 2.0)));
+        assert!(image.contains(v(0.5, 1.5)));
+        assert!(!image.contains(v(1.5, 1.5)));
+
+        let scaled = image.clone().scale(v(2.0, 0.5));
+        assert_eq!(scaled, Shape::Image { from: v(0.0, 0.0), to: v(2.0, 1.0),
+                                          image: Image::HoveredButton });
+
+        match image.transform(&Transform::rotation(1.0)) {
+            Shape::Transformed { .. } => (),
+            shape => panic!("{:?}", shape),
+        }
+    }
+
+    #[test]
+    fn text_with_monospace_metrics() {
+        let shape = text("abc", v(1.0, 1.0), 2.0);
+        assert_eq!(shape.bounding_box(), (v(1.0, 1.0), v(7.0, 3.0)));
+        assert!(shape.contains(v(6.0, 2.0)));
+        assert!(!shape.contains(v(7.5, 2.0)));
+
+        // uniform scales keep texts as texts, other transformations wrap them
+        assert_eq!(shape.clone().scale(v(2.0, 2.0)), text("abc", v(2.0, 2.0), 4.0));
+        match shape.clone().scale(v(2.0, 1.0)) {
+            Shape::Transformed { .. } => (),
+            shape => panic!("{:?}", shape),
+        }
+        match shape.transform(&Transform::scale(v(-1.0, -1.0))) {
+            Shape::Transformed { .. } => (),
+            shape => panic!("{:?}", shape),
+        }
+    }
+
+    #[test]
+    fn text_with_custom_metrics() {
+        let shape = text("hi", v(0.0, 0.0), 4.0);
+        assert_eq!(shape.bounding_box_with_metrics(&NarrowMetrics), (v(0.0, -1.0), v(3.0, 3.0)));
+        assert!(shape.contains_with_metrics(v(2.5, -0.5), &NarrowMetrics));
+        assert!(!shape.contains_with_metrics(v(3.5, 0.5), &NarrowMetrics));
+        assert!(!shape.contains_with_metrics(v(1.0, 3.5), &NarrowMetrics));
+
+        // the metrics are also used inside of other shapes
+        let moved = Shape::Transformed { transform: Transform::translation(v(1.0, 1.0)),
+                                         shape: Box::new(shape.clone()) };
+        assert_eq!(moved.bounding_box_with_metrics(&NarrowMetrics), (v(1.0, 0.0), v(4.0, 4.0)));
+
+        let clipped = shape.clip(v(1.0, -5.0), v(10.0, 5.0));
+        assert_eq!(clipped.bounding_box_with_metrics(&NarrowMetrics),
+                   (v(1.0, -1.0), v(3.0, 3.0)));
+    }
+
+    #[test]
+    fn empty_text() {
+        let shape = text("", v(1.0, 1.0), 1.0);
+        assert_eq!(shape.bounding_box(), (v(1.0, 1.0), v(1.0, 2.0)));
+        assert!(!shape.contains(v(1.0, 1.5)));
+    }
+
+    #[test]
+    fn clipped() {
+        let shape = rectangle(v(0.0, 0.0), v(4.0, 4.0)).clip(v(2.0, 2.0), v(6.0, 6.0));
+        assert_eq!(shape.bounding_box(), (v(2.0, 2.0), v(4.0, 4.0)));
+        assert!(shape.contains(v(3.0, 3.0)));
+        assert!(!shape.contains(v(1.0, 1.0)));
+        assert!(!shape.contains(v(5.0, 5.0)));
+
+        let moved = shape.clone().translate(v(1.0, 0.0));
+        assert_eq!(moved.bounding_box(), (v(3.0, 2.0), v(5.0, 4.0)));
+        assert_eq!(moved, shape.clone().transform(&Transform::translation(v(1.0, 0.0))));
+
+        // rotating a clip rectangle can't be represented by a `Clipped`
+        match shape.transform(&Transform::rotation(1.0)) {
+            Shape::Transformed { ref shape, .. } => match **shape {
+                Shape::Clipped { .. } => (),
+                ref shape => panic!("{:?}", shape),
+            },
+            shape => panic!("{:?}", shape),
+        }
+    }
+
+    #[test]
+    fn nested_clips() {
+        let shape = rectangle(v(0.0, 0.0), v(10.0, 10.0)).clip(v(2.0, 2.0), v(20.0, 20.0))
+                                                          .clip(v(0.0, 0.0), v(5.0, 5.0));
+        assert_eq!(shape, Shape::Clipped { from: v(2.0, 2.0), to: v(5.0, 5.0),
+                                           shape: Box::new(rectangle(v(0.0, 0.0),
+                                                                     v(10.0, 10.0))) });
+        assert_eq!(shape.bounding_box(), (v(2.0, 2.0), v(5.0, 5.0)));
+        assert!(shape.contains(v(4.0, 4.0)));
+        assert!(!shape.contains(v(6.0, 6.0)));
+
+        // clips separated by a transformation are not merged, but still intersect
+        let rotated = rectangle(v(-5.0, -5.0), v(5.0, 5.0)).clip(v(-1.0, -1.0), v(1.0, 1.0))
+                          .transform(&Transform::rotation(PI / 4.0))
+                          .clip(v(0.0, -5.0), v(5.0, 5.0));
+        let half_diagonal = 2.0f32.sqrt();
+        assert_close(rotated.bounding_box(),
+                     (v(0.0, -half_diagonal), v(half_diagonal, half_diagonal)));
+        assert!(rotated.contains(v(0.5, 0.0)));
+        assert!(!rotated.contains(v(-0.5, 0.0)));
+        assert!(!rotated.contains(v(1.0, 1.0)));
+
+        // disjoint clips show nothing
+        let hidden = rectangle(v(0.0, 0.0), v(10.0, 10.0)).clip(v(0.0, 0.0), v(1.0, 1.0))
+                                                           .clip(v(5.0, 5.0), v(6.0, 6.0));
+        assert_eq!(hidden.clone().apply_clip(), None);
+        assert!(!hidden.contains(v(0.5, 0.5)));
+    }
+
+    #[test]
+    fn apply_clip() {
+        let inside = rectangle(v(1.0, 1.0), v(2.0, 2.0));
+        assert_eq!(inside.clone().clip(v(0.0, 0.0), v(5.0, 5.0)).apply_clip(),
+                   Some(inside.clone()));
+        assert_eq!(inside.clone().clip(v(1.5, 0.0), v(5.0, 5.0)).apply_clip(),
+                   Some(rectangle(v(1.5, 1.0), v(2.0, 2.0))));
+
+        let point = Shape::Point { location: v(1.0, 1.0), color: [1.0; 3] };
+        assert_eq!(point.clone().clip(v(0.0, 0.0), v(2.0, 2.0)).apply_clip(), Some(point.clone()));
+        assert_eq!(point.clip(v(2.0, 2.0), v(3.0, 3.0)).apply_clip(), None);
+
+        let line = Shape::Line { from: v(-1.0, -1.0), to: v(3.0, 3.0), color: [1.0; 3] };
+        assert_eq!(line.clone().clip(v(0.0, 0.0), v(2.0, 2.0)).apply_clip(),
+                   Some(Shape::Line { from: v(0.0, 0.0), to: v(2.0, 2.0), color: [1.0; 3] }));
+        assert_eq!(line.clip(v(2.0, -2.0), v(3.0, 0.0)).apply_clip(), None);
+
+        // texts partly outside stay clipped, the other ones are kept or removed
+        let partial = text("abc", v(0.0, 0.0), 1.0).clip(v(0.0, 0.0), v(2.0, 1.0));
+        assert_eq!(partial.clone().apply_clip(), Some(partial));
+        let whole = text("abc", v(0.0, 0.0), 1.0);
+        assert_eq!(whole.clone().clip(v(0.0, 0.0), v(5.0, 1.0)).apply_clip(), Some(whole.clone()));
+        assert_eq!(whole.clip(v(4.0, 0.0), v(5.0, 1.0)).apply_clip(), None);
+
+        // shapes without clip are unchanged
+        assert_eq!(inside.clone().apply_clip(), Some(inside));
+    }
+
+    #[test]
+    fn bounding_box_of() {
+        assert_eq!(Shape::bounding_box_of(&[]), None);
+
+        let shapes = [
+            rectangle(v(0.0, 0.0), v(1.0, 1.0)),
+            Shape::Point { location: v(-2.0, 0.5), color: [1.0; 3] },
+            text("ab", v(0.0, 3.0), 1.0),
+        ];
+
+        assert_eq!(Shape::bounding_box_of(&shapes), Some((v(-2.0, 0.0), v(2.0, 4.0))));
+        assert_eq!(Shape::bounding_box_of(&shapes[.. 1]), Some((v(0.0, 0.0), v(1.0, 1.0))));
+    }
+
+    #[test]
+    fn flip_vertically() {
+        assert_eq!(rectangle(v(0.0, 1.0), v(2.0, 3.0)).flip_vertically(10.0),
+                   rectangle(v(0.0, 9.0), v(2.0, 7.0)));
+
+        // texts stay upright, so their box is mirrored
+        assert_eq!(text("a", v(1.0, 1.0), 2.0).flip_vertically(10.0),
+                   text("a", v(1.0, 7.0), 2.0));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    extern crate serde_json;
+
+    use std::fmt::Debug;
+    use nalgebra::Vec2;
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+
+    use transform::Transform;
+    use super::{Font, Image, Shape};
+
+    fn round_trip<T>(value: T) where T: Serialize + DeserializeOwned + PartialEq + Debug {
+        let json = serde_json::to_string(&value).unwrap();
+        let decoded: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    fn rectangle() -> Shape {
+        Shape::Rectangle { from: Vec2::new(-1.0, 0.25), to: Vec2::new(0.5, 1.0),
+                           color: [1.0, 0.5, 0.0] }
+    }
+
+    #[test]
+    fn point() {
+        round_trip(Shape::Point { location: Vec2::new(0.1, -0.2), color: [0.0, 0.0, 1.0] });
+    }
+
+    #[test]
+    fn line() {
+        round_trip(Shape::Line { from: Vec2::new(0.0, 0.0), to: Vec2::new(1.5, -2.0),
+                                 color: [0.3, 0.6, 0.9] });
+    }
+
+    #[test]
+    fn rectangle_shape() {
+        round_trip(rectangle());
+    }
+
+    #[test]
+    fn image() {
+        for image in vec![Image::UnhoveredButton, Image::HoveredButton,
+                          Image::Custom("icons/close.png".to_string())]
+        {
+            round_trip(image.clone());
+            round_trip(Shape::Image { from: Vec2::new(0.0, 0.0), to: Vec2::new(0.5, 0.5),
+                                      image: image });
+        }
+    }
+
+    #[test]
+    fn text() {
+        for font in vec![Font::Default, Font::Button, Font::Custom("Arial".to_string())] {
+            round_trip(font.clone());
+            round_trip(Shape::Text { text: "hello \"world\"\n".to_string(), font: font,
+                                     bottom_left: Vec2::new(-0.5, 0.125), em: 0.1 });
+        }
+    }
+
+    #[test]
+    fn transformed() {
+        let transform = Transform::rotation(0.5)
+                            .then(&Transform::translation(Vec2::new(1.0, 2.0)));
+        round_trip(transform);
+        round_trip(Shape::Transformed { transform: transform, shape: Box::new(rectangle()) });
+    }
+
+    #[test]
+    fn clipped() {
+        let clipped = rectangle().clip(Vec2::new(0.0, 0.0), Vec2::new(0.5, 0.5));
+        round_trip(clipped.clone());
+        round_trip(Shape::Transformed { transform: Transform::scale(Vec2::new(2.0, 3.0)),
+                                        shape: Box::new(clipped) });
+    }
 
-        self
+    #[test]
+    fn vector() {
+        let json = serde_json::to_string(&rectangle()).unwrap();
+        assert!(json.contains("[-1.0,0.25]"));
     }
 }
@@ -0,0 +1,382 @@
+//! Snapshot testing of the shapes produced by a `Ui`.
+//!
+//! Shapes are written one per line in the canonical format of their `Display` implementation
+//! and compared against a file committed alongside the tests. Set the `UI_UPDATE_SNAPSHOTS`
+//! environment variable to `1` in order to write the current shapes to the files instead. The
+//! files that change are reported on the standard error.
+//!
+//! Updating is refused when the `CI` environment variable is set, so that a forgotten
+//! `UI_UPDATE_SNAPSHOTS` can't make every snapshot test pass on a continuous integration.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use shape::Shape;
+
+/// Name of the environment variable that turns snapshot comparisons into updates.
+pub const UPDATE_VARIABLE: &'static str = "UI_UPDATE_SNAPSHOTS";
+
+/// Name of the environment variable that continuous integration services set, and that forbids
+/// updates.
+pub const CI_VARIABLE: &'static str = "CI";
+
+/// Writes a list of shapes in the snapshot format.
+pub fn serialize(shapes: &[Shape]) -> String {
+    let mut result = String::new();
+
+    for shape in shapes {
+        result.push_str(&format!("{}\n", shape));
+    }
+
+    result
+}
+
+/// Compares two snapshots. Returns `None` if they are identical, or a human-readable description
+/// of the differences otherwise.
+///
+/// Shapes are compared one by one in drawing order. For each shape that differs, the description
+/// tells whether it moved, changed color or was otherwise modified.
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    let mut result = String::new();
+
+    for index in 0 .. expected.len().max(actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(e), Some(a)) if e == a => (),
+
+            (Some(e), Some(a)) => {
+                result.push_str(&format!("shape #{} {}:\n- {}\n+ {}\n", index,
+                                         describe_change(e, a), e, a));
+            },
+
+            (Some(e), None) => {
+                result.push_str(&format!("shape #{} removed:\n- {}\n", index, e));
+            },
+
+            (None, Some(a)) => {
+                result.push_str(&format!("shape #{} added:\n+ {}\n", index, a));
+            },
+
+            (None, None) => unreachable!(),
+        }
+    }
+
+    if result.is_empty() {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+/// Compares the shapes with the snapshot stored at the given path.
+///
+/// If the `UI_UPDATE_SNAPSHOTS` environment variable is `1`, writes the shapes to the file
+/// instead.
+///
+/// # Panic
+///
+/// Panics if the snapshot doesn't exist or doesn't match, or if it would be updated while the
+/// `CI` environment variable is set.
+pub fn assert_snapshot<P>(path: P, shapes: &[Shape]) where P: AsRef<Path> {
+    let path = path.as_ref();
+    let actual = serialize(shapes);
+
+    if env::var(UPDATE_VARIABLE).ok().as_ref().map(|v| &v[..]) == Some("1") {
+        update_snapshot(path, &actual, env::var_os(CI_VARIABLE).is_some());
+        return;
+    }
+
+    let mut expected = String::new();
+    match File::open(path) {
+        Ok(mut file) => { file.read_to_string(&mut expected).unwrap(); },
+        Err(_) => panic!("Snapshot {} doesn't exist. Run with {}=1 to create it.",
+                         path.display(), UPDATE_VARIABLE),
+    };
+
+    if let Some(diff) = diff(&expected, &actual) {
+        panic!("Snapshot {} doesn't match. Run with {}=1 to update it.\n{}",
+               path.display(), UPDATE_VARIABLE, diff);
+    }
+}
+
+/// Writes a snapshot to a file, and reports it on the standard error if its content changed.
+///
+/// # Panic
+///
+/// Panics if `ci` is true.
+fn update_snapshot(path: &Path, content: &str, ci: bool) {
+    if ci {
+        panic!("Refusing to update the snapshot {} because {} is set. Unset {} to compare it \
+                instead.", path.display(), CI_VARIABLE, UPDATE_VARIABLE);
+    }
+
+    let mut previous = String::new();
+    if let Ok(mut file) = File::open(path) {
+        file.read_to_string(&mut previous).unwrap();
+        if previous == content {
+            return;
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+
+    File::create(path).unwrap().write_all(content.as_bytes()).unwrap();
+    let _ = writeln!(io::stderr(), "Updated snapshot {}", path.display());
+}
+
+/// Describes how a line of a snapshot turned into another.
+fn describe_change(expected: &str, actual: &str) -> String {
+    let (expected_kind, expected_fields) = parse_line(expected);
+    let (actual_kind, actual_fields) = parse_line(actual);
+
+    if expected_kind != actual_kind || expected_fields.len() != actual_fields.len() {
+        return "replaced".to_string();
+    }
+
+    let mut changes = Vec::new();
+
+    for (&(key, e), &(_, a)) in expected_fields.iter().zip(actual_fields.iter()) {
+        if e == a {
+            continue;
+        }
+
+        let change = match key {
+            "location" | "from" | "to" | "bottom_left" | "matrix" => "moved",
+            "color" => "changed color",
+            _ => "changed",
+        };
+
+        if !changes.contains(&change) {
+            changes.push(change);
+        }
+    }
+
+    changes.join(" and ")
+}
+
+/// Splits a line of a snapshot into the kind of shape and its list of fields.
+fn parse_line(line: &str) -> (&str, Vec<(&str, &str)>) {
+    let (kind, mut rest) = match line.find(' ') {
+        Some(pos) => (&line[.. pos], &line[pos + 1 ..]),
+        None => return (line, Vec::new()),
+    };
+
+    let mut fields = Vec::new();
+
+    while !rest.is_empty() {
+        // the text of a `Text` and the inner shape of a `Transformed` are always the last field
+        // and can contain spaces
+        let end = if rest.starts_with("text=") || rest.starts_with("shape=") {
+            rest.len()
+        } else {
+            rest.find(' ').unwrap_or(rest.len())
+        };
+
+        let field = &rest[.. end];
+        match field.find('=') {
+            Some(pos) => fields.push((&field[.. pos], &field[pos + 1 ..])),
+            None => fields.push((field, "")),
+        }
+
+        rest = if end < rest.len() { &rest[end + 1 ..] } else { "" };
+    }
+
+    (kind, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use nalgebra::Vec2;
+
+    use predefined::ButtonComponent;
+    use predefined::button::ButtonEvent;
+    use shape::{Font, Image, Shape};
+    use testing::UiTester;
+    use transform::Transform;
+    use super::{assert_snapshot, describe_change, diff, parse_line, serialize, update_snapshot};
+
+    /// Returns the path of a snapshot committed in `tests/snapshots`.
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots").join(name)
+    }
+
+    fn shapes() -> Vec<Shape> {
+        let rectangle = Shape::Rectangle {
+            from: Vec2::new(-1.0, -1.0),
+            to: Vec2::new(0.5, 0.25),
+            color: [1.0, 1.0, 0.0],
+        };
+
+        vec![
+            Shape::Point { location: Vec2::new(0.1, 0.2), color: [1.0, 0.0, 0.0] },
+            Shape::Line { from: Vec2::new(0.0, 0.0), to: Vec2::new(1.0, -0.0001),
+                          color: [0.0, 1.0, 0.0] },
+            rectangle.clone(),
+            Shape::Image { from: Vec2::new(0.0, 0.0), to: Vec2::new(0.5, 0.5),
+                           image: Image::Custom("icon".to_string()) },
+            Shape::Text { text: "hello world color=(1,1,1)".to_string(), font: Font::Button,
+                          bottom_left: Vec2::new(-0.5, 0.125), em: 0.1 },
+            Shape::Transformed { transform: Transform::rotation(0.5),
+                                 shape: Box::new(rectangle.clone()) },
+            Shape::Clipped { from: Vec2::new(0.0, 0.0), to: Vec2::new(0.5, 0.5),
+                             shape: Box::new(rectangle) },
+        ]
+    }
+
+    #[test]
+    fn matches_committed_snapshot() {
+        assert_snapshot(fixture("shapes.snap"), &shapes());
+    }
+
+    #[test]
+    fn ui_snapshot() {
+        let mut button: ButtonComponent = Default::default();
+        button.set_color([0.0, 0.5, 1.0]);
+
+        let mut tester: UiTester<_, ButtonEvent> = UiTester::new(button, Vec2::new(800, 600));
+        tester.assert_snapshot(fixture("button.snap"));
+
+        // hovering darkens the button
+        tester.move_mouse(::testing::Target::Path(Vec::new()));
+        let hovered = serialize(tester.shapes());
+        let expected = serialize(&[Shape::Rectangle { from: Vec2::new(-1.0, -1.0),
+                                                      to: Vec2::new(1.0, 1.0),
+                                                      color: [0.0, 0.5, 1.0] }]);
+        assert_eq!(describe_change(expected.trim(), hovered.trim()), "changed color");
+    }
+
+    #[test]
+    #[should_panic(expected = "shape #2 changed color")]
+    fn detects_mismatch() {
+        let mut shapes = shapes();
+        shapes[2] = Shape::Rectangle { from: Vec2::new(-1.0, -1.0), to: Vec2::new(0.5, 0.25),
+                                       color: [0.0, 0.0, 1.0] };
+        assert_snapshot(fixture("shapes.snap"), &shapes);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't exist")]
+    fn missing_snapshot() {
+        assert_snapshot(fixture("missing.snap"), &shapes());
+    }
+
+    #[test]
+    #[should_panic(expected = "Refusing to update the snapshot")]
+    fn no_update_on_ci() {
+        update_snapshot(&fixture("shapes.snap"), "", true);
+    }
+
+    #[test]
+    fn update_writes_the_file() {
+        let path = ::std::env::temp_dir().join("ui-update-snapshot.snap");
+        let content = serialize(&shapes());
+        update_snapshot(&path, &content, false);
+        assert_snapshot(&path, &shapes());
+    }
+
+    #[test]
+    fn serialize_rounds_numbers() {
+        let line = serialize(&shapes()[1 .. 2]);
+        assert_eq!(line, "line from=(0.000,0.000) to=(1.000,0.000) color=(0.000,1.000,0.000)\n");
+    }
+
+    #[test]
+    fn identical_snapshots() {
+        let snapshot = serialize(&shapes());
+        assert_eq!(diff(&snapshot, &snapshot), None);
+    }
+
+    #[test]
+    fn added_and_removed_shapes() {
+        let all = serialize(&shapes());
+        let first = serialize(&shapes()[.. 1]);
+
+        let removed = diff(&all, &first).unwrap();
+        assert!(removed.contains("shape #1 removed:\n- line"));
+        assert!(removed.contains("shape #6 removed:\n- clipped"));
+
+        let added = diff(&first, &all).unwrap();
+        assert!(added.contains("shape #1 added:\n+ line"));
+        assert!(!added.contains("shape #0"));
+    }
+
+    #[test]
+    fn moved_shape() {
+        let change = describe_change("rectangle from=(0.000,0.000) to=(1.000,1.000) \
+                                      color=(1.000,1.000,1.000)",
+                                     "rectangle from=(0.500,0.000) to=(1.500,1.000) \
+                                      color=(1.000,1.000,1.000)");
+        assert_eq!(change, "moved");
+    }
+
+    #[test]
+    fn changed_color() {
+        let change = describe_change("point location=(0.000,0.000) color=(1.000,1.000,1.000)",
+                                     "point location=(0.000,0.000) color=(0.000,1.000,1.000)");
+        assert_eq!(change, "changed color");
+    }
+
+    #[test]
+    fn moved_and_changed_color() {
+        let change = describe_change("line from=(0.000,0.000) to=(1.000,1.000) \
+                                      color=(1.000,1.000,1.000)",
+                                     "line from=(0.000,0.000) to=(2.000,1.000) \
+                                      color=(0.000,1.000,1.000)");
+        assert_eq!(change, "moved and changed color");
+    }
+
+    #[test]
+    fn changed_text() {
+        let change = describe_change("text font=default bottom_left=(0.000,0.000) em=0.100 \
+                                      text=\"a b\"",
+                                     "text font=default bottom_left=(0.000,0.000) em=0.200 \
+                                      text=\"a c\"");
+        assert_eq!(change, "changed");
+    }
+
+    #[test]
+    fn replaced_shape() {
+        let change = describe_change("point location=(0.000,0.000) color=(1.000,1.000,1.000)",
+                                     "line from=(0.000,0.000) to=(1.000,1.000) \
+                                      color=(1.000,1.000,1.000)");
+        assert_eq!(change, "replaced");
+    }
+
+    #[test]
+    fn parse_text_with_spaces() {
+        let (kind, fields) = parse_line("text font=button bottom_left=(0.000,0.125) em=0.100 \
+                                         text=\"hello world color=(1,1,1)\"");
+        assert_eq!(kind, "text");
+        assert_eq!(fields, vec![("font", "button"), ("bottom_left", "(0.000,0.125)"),
+                                ("em", "0.100"), ("text", "\"hello world color=(1,1,1)\"")]);
+    }
+
+    #[test]
+    fn parse_nested_shape() {
+        let (kind, fields) = parse_line("clipped from=(0.000,0.000) to=(0.500,0.500) \
+                                         shape=rectangle from=(0.000,0.000) to=(1.000,1.000) \
+                                         color=(1.000,1.000,1.000)");
+        assert_eq!(kind, "clipped");
+        assert_eq!(fields, vec![("from", "(0.000,0.000)"), ("to", "(0.500,0.500)"),
+                                ("shape", "rectangle from=(0.000,0.000) to=(1.000,1.000) \
+                                           color=(1.000,1.000,1.000)")]);
+    }
+
+    #[test]
+    fn changed_nested_shape() {
+        let before = Shape::Transformed { transform: Transform::identity(),
+                                          shape: Box::new(shapes()[2].clone()) };
+        let after = Shape::Transformed { transform: Transform::identity(),
+                                         shape: Box::new(shapes()[0].clone()) };
+        let change = describe_change(&format!("{}", before), &format!("{}", after));
+        assert_eq!(change, "changed");
+    }
+}
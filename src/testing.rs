@@ -0,0 +1,330 @@
+//! Tools for driving a `Ui` from tests without having to open a window.
+//!
+//! ```ignore
+//! let mut tester = UiTester::new(MyWidget::default(), Vec2::new(800, 600));
+//! tester.click(Target::TestId("increment".to_string()));
+//! assert_eq!(tester.poll_events().len(), 1);
+//! ```
+
+use std::fmt::Debug;
+use std::path::Path;
+
+use nalgebra::Vec2;
+
+use component::{MouseButton, PointerId, RawComponent, ScrollDelta};
+use shape::Shape;
+use snapshot;
+use ui::Ui;
+
+/// Designates a location of the user interface.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// A pixel of the viewport.
+    Pixel(Vec2<u32>),
+
+    /// The center of the component reached by following the given child indices from the main
+    /// component. An empty path designates the main component.
+    Path(Vec<usize>),
+
+    /// The center of the component with this test id, given by `Component::get_test_id` or by
+    /// the `set_test_id` methods of the predefined components.
+    TestId(String),
+}
+
+/// Test id of a component that implements `RawComponent` directly, such as the predefined
+/// ones. Components that implement `Component` return theirs from `get_test_id` instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestId(Option<String>);
+
+impl TestId {
+    /// Sets the identifier that tests can use to locate the component.
+    pub fn set(&mut self, id: String) {
+        self.0 = Some(id);
+    }
+
+    /// Returns the identifier, if any.
+    pub fn get(&self) -> Option<&str> {
+        self.0.as_ref().map(|id| &id[..])
+    }
+
+    /// Implements `RawComponent::find_test_id` for a component without children.
+    pub fn find(&self, id: &str) -> Option<Vec<usize>> {
+        if self.get() == Some(id) {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+}
+
+/// Wraps around a `Ui` and allows scripting its input.
+pub struct UiTester<T, E> {
+    ui: Ui<T, E>,
+}
+
+impl<T, E> UiTester<T, E> where T: RawComponent<E> {
+    /// Builds a new tester around a `Ui` with the given main component and viewport.
+    pub fn new(component: T, viewport: Vec2<u32>) -> UiTester<T, E> {
+        UiTester {
+            ui: Ui::new(component, viewport),
+        }
+    }
+
+    /// Gives access to the `Ui`.
+    pub fn get_ui(&self) -> &Ui<T, E> {
+        &self.ui
+    }
+
+    /// Gives a mutable access to the `Ui`.
+    pub fn get_mut_ui(&mut self) -> &mut Ui<T, E> {
+        &mut self.ui
+    }
+
+    /// Returns the pixel corresponding to a target.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the target doesn't exist.
+    pub fn locate(&mut self, target: &Target) -> Vec2<u32> {
+        let path = match target {
+            &Target::Pixel(pixel) => return pixel,
+            &Target::Path(ref path) => path.clone(),
+            &Target::TestId(ref id) => {
+                match self.ui.get_mut_main_component().find_test_id(id) {
+                    Some(path) => path,
+                    None => panic!("No component with the test id {:?}", id),
+                }
+            },
+        };
+
+        let size = self.ui.get_main_component_size();
+        let area = self.ui.get_mut_main_component().get_child_area(size, &path);
+        let (bottom_left, top_right) = match area {
+            Some(area) => area,
+            None => panic!("No component at the path {:?}", path),
+        };
+
+        let center = Vec2::new((bottom_left.x + top_right.x) / 2.0,
+                               (bottom_left.y + top_right.y) / 2.0);
+        self.ui.logical_to_pixels(center + self.ui.get_main_component_position())
+    }
+
+    /// Moves the mouse over the target.
+    pub fn move_mouse(&mut self, target: Target) {
+        let pixel = self.locate(&target);
+        self.ui.set_mouse_position(Some(pixel));
+    }
+
+    /// Moves the mouse out of the window.
+    pub fn move_mouse_out(&mut self) {
+        self.ui.set_mouse_position(None);
+    }
+
+    /// Presses the mouse.
+    pub fn press(&mut self) {
+        self.ui.set_mouse_pressed(true);
+    }
+
+    /// Releases the mouse.
+    pub fn release(&mut self) {
+        self.ui.set_mouse_pressed(false);
+    }
+
+    /// Presses a button of the mouse.
+    pub fn press_button(&mut self, button: MouseButton) {
+        self.ui.set_mouse_button_pressed(button, true);
+    }
+
+    /// Releases a button of the mouse.
+    pub fn release_button(&mut self, button: MouseButton) {
+        self.ui.set_mouse_button_pressed(button, false);
+    }
+
+    /// Moves a pointer over the target.
+    pub fn move_pointer(&mut self, pointer: PointerId, target: Target) {
+        let pixel = self.locate(&target);
+        self.ui.set_pointer_position(pointer, Some(pixel));
+    }
+
+    /// Touches the target with a finger or a pen, without releasing it.
+    pub fn touch(&mut self, pointer: PointerId, target: Target) {
+        self.move_pointer(pointer, target);
+        self.ui.set_pointer_button_pressed(pointer, MouseButton::Left, true);
+    }
+
+    /// Stops touching the screen with a finger or a pen.
+    pub fn end_touch(&mut self, pointer: PointerId) {
+        self.ui.set_pointer_button_pressed(pointer, MouseButton::Left, false);
+        self.ui.remove_pointer(pointer);
+    }
+
+    /// Moves the mouse over the target, then presses and releases it.
+    pub fn click(&mut self, target: Target) {
+        self.move_mouse(target);
+        self.press();
+        self.release();
+    }
+
+    /// Advances the time of the `Ui` by the given number of seconds.
+    pub fn tick(&mut self, elapsed: f32) {
+        self.ui.tick(elapsed);
+    }
+
+    /// Returns true if some components of the `Ui` are animating.
+    pub fn is_animating(&self) -> bool {
+        self.ui.is_animating()
+    }
+
+    /// Uses the mouse wheel at the current position of the mouse.
+    pub fn scroll(&mut self, delta: ScrollDelta) {
+        self.ui.scroll(delta);
+    }
+
+    /// Sends each character of the text to the component under the mouse.
+    pub fn type_text(&mut self, text: &str) {
+        for character in text.chars() {
+            self.ui.send_character(character);
+        }
+    }
+
+    /// Returns the events produced since the last call to this function.
+    pub fn poll_events(&mut self) -> Vec<E> {
+        self.ui.poll_events()
+    }
+
+    /// Returns the shapes currently produced by the `Ui`.
+    pub fn shapes(&self) -> &[Shape] {
+        self.ui.draw()
+    }
+
+    /// Returns the shapes that match the predicate.
+    pub fn find_shapes<F>(&self, mut predicate: F) -> Vec<&Shape> where F: FnMut(&Shape) -> bool {
+        self.ui.draw().iter().filter(|s| predicate(s)).collect()
+    }
+
+    /// Panics if none of the shapes match the predicate.
+    pub fn assert_has_shape<F>(&self, predicate: F) where F: FnMut(&Shape) -> bool {
+        if self.find_shapes(predicate).is_empty() {
+            panic!("No shape matches the predicate. Shapes: {:#?}", self.shapes());
+        }
+    }
+
+    /// Panics if the shapes are not exactly the expected ones.
+    pub fn assert_shapes(&self, expected: &[Shape]) {
+        if self.shapes() != expected {
+            panic!("Shapes don't match.\nExpected: {:#?}\nGot: {:#?}", expected, self.shapes());
+        }
+    }
+
+    /// Compares the shapes with the snapshot stored at the given path. See the `snapshot` module.
+    pub fn assert_snapshot<P>(&self, path: P) where P: AsRef<Path> {
+        snapshot::assert_snapshot(path, self.shapes())
+    }
+}
+
+impl<T, E> UiTester<T, E> where T: RawComponent<E>, E: Debug + PartialEq {
+    /// Polls the events and panics if they are not the expected ones.
+    pub fn assert_events(&mut self, expected: &[E]) {
+        let events = self.poll_events();
+        if &events[..] != expected {
+            panic!("Events don't match.\nExpected: {:?}\nGot: {:?}", expected, events);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vec2;
+
+    use Component;
+    use Layout;
+    use predefined::{ButtonComponent, InputComponent};
+    use predefined::button::ButtonEvent;
+    use shape::Font;
+    use super::{Target, UiTester};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum CounterEvent {
+        Increment,
+        Decrement,
+    }
+
+    struct Counter {
+        decrement: ButtonComponent,
+        increment: ButtonComponent,
+        name: InputComponent,
+    }
+
+    impl Counter {
+        fn new() -> Counter {
+            let mut decrement: ButtonComponent = Default::default();
+            decrement.set_test_id("decrement".to_string());
+            let mut increment: ButtonComponent = Default::default();
+            increment.set_test_id("increment".to_string());
+            let mut name = InputComponent::new("ab".to_string(), Font::Default, 16.0);
+            name.set_test_id("name".to_string());
+
+            Counter { decrement: decrement, increment: increment, name: name }
+        }
+    }
+
+    impl Component for Counter {
+        type EmittedEvent = CounterEvent;
+        type ReceivedEvent = ButtonEvent;
+
+        fn get_layout(&mut self) -> Layout<ButtonEvent> {
+            Layout::HorizontalBox(vec![&mut self.decrement, &mut self.increment, &mut self.name],
+                                  10.0)
+        }
+
+        fn handle_child_event(&mut self, child_id: usize, _: ButtonEvent)
+                              -> Option<CounterEvent>
+        {
+            match child_id {
+                0 => Some(CounterEvent::Decrement),
+                1 => Some(CounterEvent::Increment),
+                _ => None,
+            }
+        }
+    }
+
+    fn tester() -> UiTester<Counter, CounterEvent> {
+        UiTester::new(Counter::new(), Vec2::new(800, 600))
+    }
+
+    #[test]
+    fn locate_by_test_id() {
+        let mut tester = tester();
+        assert_eq!(tester.locate(&Target::TestId("decrement".to_string())), Vec2::new(50, 585));
+        assert_eq!(tester.locate(&Target::TestId("increment".to_string())), Vec2::new(160, 585));
+        assert_eq!(tester.locate(&Target::Path(vec![1])), Vec2::new(160, 585));
+    }
+
+    #[test]
+    #[should_panic]
+    fn locate_unknown_test_id() {
+        let mut tester = tester();
+        tester.locate(&Target::TestId("unknown".to_string()));
+    }
+
+    #[test]
+    fn click_buttons() {
+        let mut tester = tester();
+        tester.click(Target::TestId("increment".to_string()));
+        tester.click(Target::TestId("increment".to_string()));
+        tester.click(Target::TestId("decrement".to_string()));
+        tester.assert_events(&[CounterEvent::Increment, CounterEvent::Increment,
+                               CounterEvent::Decrement]);
+
+        tester.click(Target::Pixel(Vec2::new(700, 100)));
+        tester.assert_events(&[]);
+    }
+
+    #[test]
+    fn type_text() {
+        let mut tester = tester();
+        tester.move_mouse(Target::TestId("name".to_string()));
+        tester.type_text("c\x08d");
+        assert_eq!(tester.get_ui().get_main_component().name.get_text(), "abd");
+    }
+}
@@ -0,0 +1,195 @@
+use nalgebra::Vec2;
+
+/// A 2D affine transformation.
+///
+/// A position `(x, y)` is turned into `(m[0][0] * x + m[0][1] * y + m[0][2],
+/// m[1][0] * x + m[1][1] * y + m[1][2])`, where `m` is the `matrix`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transform {
+    /// The first two rows of the 3x3 matrix of the transformation.
+    pub matrix: [[f32; 3]; 2],
+}
+
+impl Transform {
+    /// Builds the transformation that doesn't change anything.
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: [
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+        }
+    }
+
+    /// Builds a transformation that moves positions by the given vector.
+    pub fn translation(vec: Vec2<f32>) -> Transform {
+        Transform {
+            matrix: [
+                [1.0, 0.0, vec.x],
+                [0.0, 1.0, vec.y],
+            ],
+        }
+    }
+
+    /// Builds a transformation that multiplies positions by the given factors, around
+    /// `(0.0, 0.0)`.
+    pub fn scale(factors: Vec2<f32>) -> Transform {
+        Transform {
+            matrix: [
+                [factors.x, 0.0, 0.0],
+                [0.0, factors.y, 0.0],
+            ],
+        }
+    }
+
+    /// Builds a transformation that rotates positions counter-clockwise by the given angle in
+    /// radians, around `(0.0, 0.0)`.
+    pub fn rotation(angle: f32) -> Transform {
+        let (sin, cos) = (angle.sin(), angle.cos());
+
+        Transform {
+            matrix: [
+                [cos, -sin, 0.0],
+                [sin, cos, 0.0],
+            ],
+        }
+    }
+
+    /// Returns the transformation that applies `self`, then `other`.
+    pub fn then(&self, other: &Transform) -> Transform {
+        let a = &other.matrix;
+        let b = &self.matrix;
+
+        Transform {
+            matrix: [
+                [a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                 a[0][0] * b[0][1] + a[0][1] * b[1][1],
+                 a[0][0] * b[0][2] + a[0][1] * b[1][2] + a[0][2]],
+                [a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                 a[1][0] * b[0][1] + a[1][1] * b[1][1],
+                 a[1][0] * b[0][2] + a[1][1] * b[1][2] + a[1][2]],
+            ],
+        }
+    }
+
+    /// Returns the transformation that cancels this one, or `None` if this transformation
+    /// flattens positions on a line or a point.
+    pub fn inverse(&self) -> Option<Transform> {
+        let m = &self.matrix;
+        let determinant = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+
+        if determinant == 0.0 {
+            return None;
+        }
+
+        let a = m[1][1] / determinant;
+        let b = -m[0][1] / determinant;
+        let c = -m[1][0] / determinant;
+        let d = m[0][0] / determinant;
+
+        Some(Transform {
+            matrix: [
+                [a, b, -(a * m[0][2] + b * m[1][2])],
+                [c, d, -(c * m[0][2] + d * m[1][2])],
+            ],
+        })
+    }
+
+    /// Applies the transformation to a position.
+    pub fn apply(&self, position: Vec2<f32>) -> Vec2<f32> {
+        let m = &self.matrix;
+        Vec2::new(m[0][0] * position.x + m[0][1] * position.y + m[0][2],
+                  m[1][0] * position.x + m[1][1] * position.y + m[1][2])
+    }
+
+    /// Returns true if the transformation keeps rectangles perpendicular to the screen, in other
+    /// words if it only contains translations and scales.
+    pub fn is_axis_aligned(&self) -> bool {
+        self.matrix[0][1] == 0.0 && self.matrix[1][0] == 0.0
+    }
+
+    /// Returns the translation part of the transformation.
+    pub fn get_translation(&self) -> Vec2<f32> {
+        Vec2::new(self.matrix[0][2], self.matrix[1][2])
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use nalgebra::Vec2;
+
+    use super::Transform;
+
+    fn assert_close(a: Vec2<f32>, b: Vec2<f32>) {
+        if (a.x - b.x).abs() > 1e-4 || (a.y - b.y).abs() > 1e-4 {
+            panic!("{:?} is not close to {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn apply() {
+        let position = Vec2::new(2.0, 3.0);
+        assert_eq!(Transform::identity().apply(position), position);
+        assert_eq!(Transform::translation(Vec2::new(1.0, -1.0)).apply(position),
+                   Vec2::new(3.0, 2.0));
+        assert_eq!(Transform::scale(Vec2::new(2.0, -1.0)).apply(position), Vec2::new(4.0, -3.0));
+        assert_close(Transform::rotation(PI / 2.0).apply(position), Vec2::new(-3.0, 2.0));
+    }
+
+    #[test]
+    fn then() {
+        let scale_then_move = Transform::scale(Vec2::new(2.0, 2.0))
+                                  .then(&Transform::translation(Vec2::new(1.0, 0.0)));
+        assert_eq!(scale_then_move.apply(Vec2::new(1.0, 1.0)), Vec2::new(3.0, 2.0));
+
+        let move_then_scale = Transform::translation(Vec2::new(1.0, 0.0))
+                                  .then(&Transform::scale(Vec2::new(2.0, 2.0)));
+        assert_eq!(move_then_scale.apply(Vec2::new(1.0, 1.0)), Vec2::new(4.0, 2.0));
+
+        let rotation = Transform::rotation(PI / 4.0);
+        assert_close(rotation.then(&rotation).apply(Vec2::new(1.0, 0.0)), Vec2::new(0.0, 1.0));
+        assert_eq!(rotation.then(&Transform::identity()), rotation);
+        assert_eq!(Transform::identity().then(&rotation), rotation);
+    }
+
+    #[test]
+    fn inverse() {
+        let transform = Transform::scale(Vec2::new(2.0, 0.5))
+                            .then(&Transform::rotation(0.7))
+                            .then(&Transform::translation(Vec2::new(-3.0, 4.0)));
+        let inverse = transform.inverse().unwrap();
+
+        for &position in [Vec2::new(0.0, 0.0), Vec2::new(1.0, -2.0), Vec2::new(5.0, 3.0)].iter() {
+            assert_close(inverse.apply(transform.apply(position)), position);
+            assert_close(transform.apply(inverse.apply(position)), position);
+        }
+
+        assert_eq!(Transform::identity().inverse(), Some(Transform::identity()));
+        assert_eq!(Transform::translation(Vec2::new(1.0, 2.0)).inverse(),
+                   Some(Transform::translation(Vec2::new(-1.0, -2.0))));
+    }
+
+    #[test]
+    fn singular() {
+        assert_eq!(Transform::scale(Vec2::new(0.0, 1.0)).inverse(), None);
+        assert_eq!(Transform { matrix: [[1.0, 2.0, 0.0], [2.0, 4.0, 1.0]] }.inverse(), None);
+    }
+
+    #[test]
+    fn axis_aligned() {
+        assert!(Transform::scale(Vec2::new(2.0, -1.0)).is_axis_aligned());
+        assert!(!Transform::rotation(0.5).is_axis_aligned());
+        assert_eq!(Transform::translation(Vec2::new(1.0, 2.0))
+                       .then(&Transform::scale(Vec2::new(2.0, 2.0)))
+                       .get_translation(),
+                   Vec2::new(2.0, 4.0));
+    }
+}
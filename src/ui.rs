@@ -1,11 +1,14 @@
 use std::ops::{Deref, DerefMut};
 use std::cmp;
+use std::mem;
 use std::marker::PhantomData;
 
 use nalgebra::Vec2;
 use shape::Shape;
+use transform::Transform;
 
-use component::RawComponent;
+use drag::{Drag, DragEvent};
+use component::{BoxConstraints, MouseButton, PointerEvent, PointerId, RawComponent, ScrollDelta};
 
 /// The main struct of this library. Manages the whole user interface.
 pub struct Ui<T, E> {
@@ -13,8 +16,126 @@ pub struct Ui<T, E> {
     marker: PhantomData<E>,
     shapes: Vec<Shape>,
     viewport: Vec2<u32>,
-    mouse: Option<Vec2<u32>>,
-    mouse_pressed: bool,
+    /// Number of physical pixels per logical pixel.
+    dpi_factor: f32,
+    unit: Unit,
+    origin: Origin,
+    /// Size chosen by the main component during the last update.
+    size: Vec2<f32>,
+    pointers: Vec<Pointer>,
+    events: Vec<E>,
+    time: f64,
+    click_settings: ClickSettings,
+    /// Whether the main component was animating during the last update.
+    animating: bool,
+}
+
+/// Unit in which the components express positions and sizes.
+///
+/// Whatever the unit, the shapes returned by `Ui::draw` are in normalized coordinates.
+///
+/// **Breaking change:** the components used to be laid out in normalized coordinates, but the
+/// default unit is now `LogicalPixels`. To keep the previous sizes and positions, call
+/// `ui.set_unit(Unit::Normalized)` right after `Ui::new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// The viewport goes from `(-1.0, -1.0)` at its bottom-left hand corner to `(1.0, 1.0)` at
+    /// its top-right hand corner, whatever its number of pixels, like the normalized coordinates
+    /// of the shapes. Components are stretched when the viewport isn't square.
+    Normalized,
+
+    /// Pixels of the viewport divided by the DPI factor, so that components keep the same
+    /// physical size on high-density screens.
+    LogicalPixels,
+
+    /// Pixels of the viewport.
+    PhysicalPixels,
+
+    /// The viewport goes from `-1.0` to `1.0` vertically, and horizontally from minus to plus its
+    /// aspect ratio, so that a unit has the same length on both axes.
+    NormalizedHeight,
+}
+
+impl Default for Unit {
+    fn default() -> Unit {
+        Unit::LogicalPixels
+    }
+}
+
+/// Corner of the viewport where the main component has its origin, and direction of the axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// `x` goes right and `y` goes up.
+    BottomLeft,
+
+    /// `x` goes right and `y` goes down, like the pixels of the viewport.
+    ///
+    /// Layouts still grow from the origin, so vertical boxes go downwards and the `bottom` of
+    /// insets is the side closest to the top of the viewport.
+    ///
+    /// The shapes are mirrored with `Shape::flip_vertically`, which means that the
+    /// `bottom_left` of texts designates their top-left hand corner.
+    TopLeft,
+}
+
+impl Default for Origin {
+    fn default() -> Origin {
+        Origin::BottomLeft
+    }
+}
+
+/// Thresholds used to detect clicks, double clicks and long presses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickSettings {
+    /// Maximum number of seconds between two clicks for them to form a double click.
+    pub double_click_time: f32,
+
+    /// Maximum distance in pixels that the pointer can travel between a press and a release for
+    /// them to form a click, and between two clicks for them to form a double click.
+    pub click_distance: f32,
+
+    /// Number of seconds a button must be held for a long press.
+    pub long_press_time: f32,
+}
+
+impl Default for ClickSettings {
+    fn default() -> ClickSettings {
+        ClickSettings {
+            double_click_time: 0.5,
+            click_distance: 4.0,
+            long_press_time: 0.8,
+        }
+    }
+}
+
+/// State of a pointer over the user interface.
+struct Pointer {
+    id: PointerId,
+    position: Option<Vec2<u32>>,
+    /// Whether the left button is pressed.
+    pressed: bool,
+    presses: Vec<ButtonPress>,
+    last_click: Option<ButtonPress>,
+    /// Path of the deepest component under the pointer.
+    hovered: Option<Vec<usize>>,
+    /// Path of the component that captured the pointer.
+    capture: Option<Vec<usize>>,
+    /// Drag in progress with this pointer.
+    drag: Option<Drag>,
+}
+
+/// A button that has been pressed or clicked.
+#[derive(Debug, Clone)]
+struct ButtonPress {
+    button: MouseButton,
+    time: f64,
+    position: Vec2<u32>,
+    long_press: bool,
+    /// True if the pointer moved far enough to start a drag.
+    drag_checked: bool,
+    /// Path of the component that was under the pointer when the button was pressed. Receives
+    /// all the pointer events related to this press.
+    target: Option<Vec<usize>>,
 }
 
 /// Allows mutable access to the main component of the `Ui`.
@@ -23,14 +144,26 @@ pub struct UiMainComponentMutRef<'a, T: 'a, E: 'a> where T: RawComponent<E> {
 }
 
 impl<T, E> Ui<T, E> where T: RawComponent<E> {
+    /// Builds a user interface around its main component, for a viewport of the given number of
+    /// pixels.
+    ///
+    /// The components are laid out in logical pixels. Call `set_unit(Unit::Normalized)` to lay
+    /// them out in normalized coordinates like before units existed.
     pub fn new(component: T, viewport: Vec2<u32>) -> Ui<T, E> {
         let mut ui = Ui {
             main_component: component,
             marker: PhantomData,
             shapes: Vec::new(),
             viewport: viewport,
-            mouse: None,
-            mouse_pressed: false,
+            dpi_factor: 1.0,
+            unit: Default::default(),
+            origin: Default::default(),
+            size: Vec2::new(0.0, 0.0),
+            pointers: Vec::new(),
+            events: Vec::new(),
+            time: 0.0,
+            click_settings: Default::default(),
+            animating: false,
         };
 
         ui.update();
@@ -43,16 +176,315 @@ impl<T, E> Ui<T, E> where T: RawComponent<E> {
         self.update();
     }
 
+    /// Sets the number of physical pixels per logical pixel, as reported by the windowing
+    /// system. The default is `1.0`.
+    pub fn set_dpi_factor(&mut self, factor: f32) {
+        self.dpi_factor = factor;
+        self.update();
+    }
+
+    /// Returns the number of physical pixels per logical pixel.
+    pub fn get_dpi_factor(&self) -> f32 {
+        self.dpi_factor
+    }
+
+    /// Sets the unit in which the components express positions and sizes. The default is
+    /// `Unit::LogicalPixels`, which replaced the normalized coordinates of the previous versions.
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.unit = unit;
+        self.update();
+    }
+
+    /// Returns the unit in which the components express positions and sizes.
+    pub fn get_unit(&self) -> Unit {
+        self.unit
+    }
+
+    /// Sets the corner of the viewport where the main component has its origin. The default is
+    /// `Origin::BottomLeft`.
+    pub fn set_origin(&mut self, origin: Origin) {
+        self.origin = origin;
+        self.update();
+    }
+
+    /// Returns the corner of the viewport where the main component has its origin.
+    pub fn get_origin(&self) -> Origin {
+        self.origin
+    }
+
     /// Changes the position of the mouse over the UI.
     pub fn set_mouse_position(&mut self, position: Option<Vec2<u32>>) {
-        self.mouse = position;
-        self.update();
+        self.set_pointer_position(PointerId::Mouse, position);
     }
 
-    /// Sets whether the mouse is pressed.
+    /// Sets whether the left button of the mouse is pressed.
     pub fn set_mouse_pressed(&mut self, pressed: bool) {
-        self.mouse_pressed = pressed;
+        self.set_mouse_button_pressed(MouseButton::Left, pressed);
+    }
+
+    /// Sets whether a button of the mouse is pressed.
+    pub fn set_mouse_button_pressed(&mut self, button: MouseButton, pressed: bool) {
+        self.set_pointer_button_pressed(PointerId::Mouse, button, pressed);
+    }
+
+    /// Changes the position of a pointer over the UI.
+    ///
+    /// For touch screens, call this when a finger starts touching the screen and whenever it
+    /// moves, alongside `set_pointer_button_pressed` with `MouseButton::Left`.
+    pub fn set_pointer_position(&mut self, pointer: PointerId, position: Option<Vec2<u32>>) {
+        let index = self.pointer_index(pointer);
+        self.pointers[index].position = position;
+        self.update();
+
+        self.update_drag(index);
+        if self.pointers[index].drag.is_some() {
+            self.update();
+        }
+    }
+
+    /// Sets whether a button of a pointer is pressed.
+    ///
+    /// Components receive a `PointerEvent` for the press or release, and for the clicks and
+    /// double clicks that it forms. All the events related to a press are delivered to the
+    /// component that was under the pointer when the button was pressed.
+    ///
+    /// When a button is pressed, the component under the pointer or one of its ancestors can
+    /// capture the pointer. See `RawComponent::get_capture_path`.
+    pub fn set_pointer_button_pressed(&mut self, pointer: PointerId, button: MouseButton,
+                                      pressed: bool)
+    {
+        let index = self.pointer_index(pointer);
+
+        if button == MouseButton::Left {
+            self.pointers[index].pressed = pressed;
+        }
+
+        let already_pressed = self.pointers[index].presses.iter().any(|p| p.button == button);
+
+        if pressed && !already_pressed {
+            if let Some(position) = self.pointers[index].position {
+                let target = self.pointers[index].hovered.clone();
+
+                self.pointers[index].presses.push(ButtonPress {
+                    button: button,
+                    time: self.time,
+                    position: position,
+                    long_press: false,
+                    drag_checked: false,
+                    target: target.clone(),
+                });
+
+                self.dispatch_pointer_event(index, target.clone(), PointerEvent::Pressed(button));
+
+                if let (true, Some(target)) = (self.pointers[index].capture.is_none(), target) {
+                    let capture = self.main_component.get_capture_path(&target, pointer, button);
+                    self.pointers[index].capture = capture;
+                }
+            }
+
+        } else if !pressed && already_pressed {
+            let press = {
+                let presses = &mut self.pointers[index].presses;
+                let position = presses.iter().position(|p| p.button == button).unwrap();
+                presses.remove(position)
+            };
+
+            self.dispatch_pointer_event(index, press.target.clone(),
+                                        PointerEvent::Released(button));
+
+            let dragged = if button == MouseButton::Left {
+                match self.pointers[index].drag.take() {
+                    Some(drag) => { self.finish_drag(drag, true); true },
+                    None => false,
+                }
+            } else {
+                false
+            };
+
+            let close = match self.pointers[index].position {
+                Some(position) => is_close(&self.click_settings, press.position, position),
+                None => false,
+            };
+
+            if !press.long_press && !dragged && close {
+                self.dispatch_pointer_event(index, press.target.clone(),
+                                            PointerEvent::Click(button));
+
+                let double_click = match self.pointers[index].last_click {
+                    Some(ref last) => {
+                        last.button == button && last.target == press.target &&
+                        is_close(&self.click_settings, last.position, press.position) &&
+                        self.time - last.time <= self.click_settings.double_click_time as f64
+                    },
+                    None => false,
+                };
+
+                if double_click {
+                    self.pointers[index].last_click = None;
+                    self.dispatch_pointer_event(index, press.target,
+                                                PointerEvent::DoubleClick(button));
+                } else {
+                    self.pointers[index].last_click = Some(ButtonPress { time: self.time, .. press });
+                }
+            }
+
+            // the component that captured the pointer receives the release before losing it
+            let all_released = self.pointers[index].presses.is_empty();
+            if all_released && self.pointers[index].capture.is_some() {
+                self.update();
+                self.pointers[index].capture = None;
+            }
+        }
+
+        self.update();
+    }
+
+    /// Removes a pointer from the UI, for example when a finger stops touching the screen.
+    ///
+    /// The buttons that are still pressed are released without producing any click.
+    pub fn remove_pointer(&mut self, pointer: PointerId) {
+        let index = match self.pointers.iter().position(|p| p.id == pointer) {
+            Some(index) => index,
+            None => return,
+        };
+
+        if let Some(drag) = self.pointers[index].drag.take() {
+            self.finish_drag(drag, false);
+        }
+
+        let presses = mem::replace(&mut self.pointers[index].presses, Vec::new());
+        for press in presses {
+            self.dispatch_pointer_event(index, press.target, PointerEvent::Released(press.button));
+        }
+
+        self.pointers[index].position = None;
+        self.pointers[index].pressed = false;
+        self.pointers[index].capture = None;
         self.update();
+
+        self.pointers.remove(index);
+    }
+
+    /// Returns the drag in progress with a pointer, if any.
+    pub fn get_drag(&self, pointer: PointerId) -> Option<&Drag> {
+        self.pointers.iter().find(|p| p.id == pointer).and_then(|p| p.drag.as_ref())
+    }
+
+    /// Returns the path of child indices leading to the component that captured a pointer, if
+    /// any.
+    pub fn get_capture(&self, pointer: PointerId) -> Option<&[usize]> {
+        self.pointers.iter().find(|p| p.id == pointer)
+                     .and_then(|p| p.capture.as_ref()).map(|path| &path[..])
+    }
+
+    /// Returns the path of child indices leading to the deepest component under a pointer, or
+    /// `None` if the pointer is not over the UI.
+    pub fn get_hovered(&self, pointer: PointerId) -> Option<&[usize]> {
+        self.pointers.iter().find(|p| p.id == pointer)
+                     .and_then(|p| p.hovered.as_ref()).map(|path| &path[..])
+    }
+
+    /// Advances the time of the user interface by the given number of seconds.
+    ///
+    /// You should call this regularly in order for double clicks and long presses to be
+    /// detected, and before each frame while `is_animating` returns true.
+    pub fn tick(&mut self, elapsed: f32) {
+        self.time += elapsed as f64;
+
+        let events = self.main_component.tick(elapsed);
+        self.events.extend(events);
+
+        let mut long_presses = Vec::new();
+
+        for (index, pointer) in self.pointers.iter_mut().enumerate() {
+            let position = pointer.position;
+
+            for press in pointer.presses.iter_mut() {
+                if press.long_press ||
+                    self.time - press.time < self.click_settings.long_press_time as f64
+                {
+                    continue;
+                }
+
+                let close = match position {
+                    Some(position) => is_close(&self.click_settings, press.position, position),
+                    None => false,
+                };
+
+                if close {
+                    press.long_press = true;
+                    long_presses.push((index, press.target.clone(), press.button));
+                }
+            }
+        }
+
+        for (index, target, button) in long_presses {
+            self.dispatch_pointer_event(index, target, PointerEvent::LongPress(button));
+        }
+
+        self.update();
+    }
+
+    /// Returns true if some components are animating.
+    ///
+    /// While this is the case, you should keep calling `tick` and redrawing. Otherwise the user
+    /// interface only changes in response to input, and you can wait for events before drawing
+    /// again.
+    pub fn is_animating(&self) -> bool {
+        self.animating
+    }
+
+    /// Returns the thresholds used to detect clicks, double clicks and long presses.
+    pub fn get_click_settings(&self) -> &ClickSettings {
+        &self.click_settings
+    }
+
+    /// Changes the thresholds used to detect clicks, double clicks and long presses.
+    pub fn set_click_settings(&mut self, settings: ClickSettings) {
+        self.click_settings = settings;
+    }
+
+    /// Tells the component under the mouse that the mouse wheel was used. Does nothing if the
+    /// mouse is not over the UI.
+    pub fn scroll(&mut self, delta: ScrollDelta) {
+        let mouse = match self.get_mouse_position() {
+            Some(mouse) => mouse,
+            None => return,
+        };
+
+        let pixel = self.get_pixel_size();
+        let delta = match delta {
+            ScrollDelta::Pixels(pixels) => {
+                ScrollDelta::Logical(Vec2::new(pixels.x * pixel.x, pixels.y * pixel.y))
+            },
+            delta => delta,
+        };
+
+        let (_, events) = self.main_component.scroll(self.size, mouse, delta);
+        self.events.extend(events);
+        self.update();
+    }
+
+    /// Sends a character typed on the keyboard to the component under the mouse.
+    pub fn send_character(&mut self, character: char) {
+        let mouse = self.get_mouse_position();
+        let events = self.main_component.send_character(self.size, mouse, character);
+        self.events.extend(events);
+        self.update();
+    }
+
+    /// Returns the list of events produced by the main component since the last call to this
+    /// function.
+    ///
+    /// The events are stored until they are polled, so you should call this regularly, for
+    /// example once per frame.
+    pub fn poll_events(&mut self) -> Vec<E> {
+        mem::replace(&mut self.events, Vec::new())
+    }
+
+    /// Gives an immutable access to the main component.
+    pub fn get_main_component(&self) -> &T {
+        &self.main_component
     }
 
     /// Gives a mutable access to the main component in order for you to modify it.
@@ -67,20 +499,382 @@ impl<T, E> Ui<T, E> where T: RawComponent<E> {
         &self.shapes
     }
 
+    /// Returns the viewport of the user interface.
+    pub fn get_viewport(&self) -> Vec2<u32> {
+        self.viewport
+    }
+
+    /// Returns the constraints given to the main component.
+    ///
+    /// The main component covers the whole viewport.
+    pub fn get_constraints(&self) -> BoxConstraints {
+        let pixel = self.get_pixel_size();
+        BoxConstraints::tight(Vec2::new(self.viewport.x as f32 * pixel.x,
+                                        self.viewport.y as f32 * pixel.y))
+    }
+
+    /// Returns the size of a pixel of the viewport in the unit of the components.
+    pub fn get_pixel_size(&self) -> Vec2<f32> {
+        match self.unit {
+            Unit::Normalized => Vec2::new(2.0 / self.viewport.x as f32,
+                                          2.0 / self.viewport.y as f32),
+            Unit::LogicalPixels => Vec2::new(1.0 / self.dpi_factor, 1.0 / self.dpi_factor),
+            Unit::PhysicalPixels => Vec2::new(1.0, 1.0),
+            Unit::NormalizedHeight => Vec2::new(2.0 / self.viewport.y as f32,
+                                                2.0 / self.viewport.y as f32),
+        }
+    }
+
+    /// Returns the transformation that turns positions relative to the main component into
+    /// normalized coordinates, where `(-1.0, -1.0)` is the bottom-left hand corner of the
+    /// viewport and `(1.0, 1.0)` the top-right hand corner.
+    ///
+    /// `draw` applies it to the shapes of the components, except that texts and images stay
+    /// upright when the origin is `Origin::TopLeft`, and that the `em` of texts is only
+    /// multiplied by the vertical scale of the transformation. Renderers must therefore multiply
+    /// the width of the letters by the height of the viewport divided by its width.
+    pub fn get_output_transform(&self) -> Transform {
+        match self.origin {
+            Origin::BottomLeft => self.get_normalization(),
+            Origin::TopLeft => {
+                let height = self.get_constraints().max.y;
+                Transform::scale(Vec2::new(1.0, -1.0))
+                    .then(&Transform::translation(Vec2::new(0.0, height)))
+                    .then(&self.get_normalization())
+            },
+        }
+    }
+
+    /// Returns the size that the main component chose during the last update.
+    pub fn get_main_component_size(&self) -> Vec2<f32> {
+        self.size
+    }
+
+    /// Returns the position of the bottom-left hand corner of the main component in the unit of
+    /// the components, or of its top-left hand corner if the origin is `Origin::TopLeft`.
+    ///
+    /// It is `(-1.0, -1.0)` with `Unit::Normalized`, so that the center of the viewport is at
+    /// `(0.0, 0.0)`, and `(0.0, 0.0)` with pixels. The positions given to the components and the
+    /// position of drags are relative to it.
+    pub fn get_main_component_position(&self) -> Vec2<f32> {
+        match self.unit {
+            Unit::Normalized => Vec2::new(-1.0, -1.0),
+            Unit::NormalizedHeight => {
+                Vec2::new(-(self.viewport.x as f32) / self.viewport.y as f32, -1.0)
+            },
+            Unit::LogicalPixels | Unit::PhysicalPixels => Vec2::new(0.0, 0.0),
+        }
+    }
+
+    /// Converts a pixel of the viewport into a position in the unit of the components, where the
+    /// main component is at `get_main_component_position`.
+    pub fn pixels_to_logical(&self, coords: Vec2<u32>) -> Vec2<f32> {
+        self.pixels_to_component(coords) + self.get_main_component_position()
+    }
+
+    /// Converts a position in the unit of the components, where the main component is at
+    /// `get_main_component_position`, into the pixel of the viewport that contains it.
+    ///
+    /// Positions outside of the viewport are clamped to its edges.
+    pub fn logical_to_pixels(&self, position: Vec2<f32>) -> Vec2<u32> {
+        let position = position - self.get_main_component_position();
+        let pixel = self.get_pixel_size();
+        let x = position.x / pixel.x;
+        let y = match self.origin {
+            Origin::BottomLeft => self.viewport.y as f32 - position.y / pixel.y,
+            Origin::TopLeft => position.y / pixel.y,
+        };
+
+        Vec2::new(
+            cmp::min(x.max(0.0) as u32, self.viewport.x.saturating_sub(1)),
+            cmp::min(y.max(0.0) as u32, self.viewport.y.saturating_sub(1)),
+        )
+    }
+
+    /// Returns the transformation that turns positions relative to the bottom-left hand corner
+    /// of the viewport into normalized coordinates.
+    fn get_normalization(&self) -> Transform {
+        let area = self.get_constraints().max;
+        Transform::scale(Vec2::new(2.0 / area.x, 2.0 / area.y))
+            .then(&Transform::translation(Vec2::new(-1.0, -1.0)))
+    }
+
+    /// Returns the position of the mouse relative to the main component.
+    fn get_mouse_position(&self) -> Option<Vec2<f32>> {
+        self.pointers.iter().find(|p| p.id == PointerId::Mouse).and_then(|p| p.position)
+                     .map(|coords| self.pixels_to_component(coords))
+    }
+
+    /// Converts a pixel of the viewport into a position relative to the main component.
+    fn pixels_to_component(&self, coords: Vec2<u32>) -> Vec2<f32> {
+        component_position(self.viewport, self.get_pixel_size(), self.origin, coords)
+    }
+
+    /// Returns the index of a pointer within `pointers`, adding it if necessary.
+    fn pointer_index(&mut self, id: PointerId) -> usize {
+        if let Some(index) = self.pointers.iter().position(|p| p.id == id) {
+            return index;
+        }
+
+        self.pointers.push(Pointer {
+            id: id,
+            position: None,
+            pressed: false,
+            presses: Vec::new(),
+            last_click: None,
+            hovered: None,
+            capture: None,
+            drag: None,
+        });
+
+        self.pointers.len() - 1
+    }
+
+    fn dispatch_pointer_event(&mut self, pointer: usize, target: Option<Vec<usize>>,
+                              event: PointerEvent)
+    {
+        let target = match target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let position = match self.pointers[pointer].position {
+            Some(position) => self.pixels_to_component(position),
+            None => return,
+        };
+
+        let id = self.pointers[pointer].id;
+        let events = self.main_component.pointer_event(self.size, &target, id, position, event);
+        self.events.extend(events);
+    }
+
+    /// Starts the drag of a pointer if it moved far enough, and updates its target.
+    fn update_drag(&mut self, index: usize) {
+        let position = match self.pointers[index].position {
+            Some(position) => position,
+            None => return,
+        };
+
+        let id = self.pointers[index].id;
+
+        if self.pointers[index].drag.is_none() {
+            let source = {
+                let settings = &self.click_settings;
+                let press = self.pointers[index].presses.iter_mut()
+                                .find(|p| p.button == MouseButton::Left);
+
+                match press {
+                    Some(press) if !press.drag_checked && !press.long_press &&
+                                           !is_close(settings, press.position, position) => {
+                        press.drag_checked = true;
+                        press.target.clone()
+                    },
+                    _ => None,
+                }
+            };
+
+            let start = match source {
+                Some(source) => self.main_component.get_drag_start(&source, id),
+                None => None,
+            };
+
+            if let Some((source, start)) = start {
+                self.pointers[index].drag = Some(Drag {
+                    source: source,
+                    payload: start.payload,
+                    visual: start.visual,
+                    target: None,
+                    position: self.pixels_to_component(position),
+                });
+            }
+        }
+
+        let hovered = self.pointers[index].hovered.clone();
+        let position = self.pixels_to_component(position);
+        let drag = match self.pointers[index].drag.as_mut() {
+            Some(drag) => drag,
+            None => return,
+        };
+
+        drag.position = position;
+
+        let target = match hovered {
+            Some(hovered) => self.main_component.get_drop_target(&hovered, &*drag.payload),
+            None => None,
+        };
+
+        if target != drag.target {
+            if let Some(previous) = drag.target.take() {
+                let events = self.main_component.drag_event(self.size, &previous, drag.position,
+                                                            DragEvent::Leave);
+                self.events.extend(events);
+            }
+
+            if let Some(ref target) = target {
+                let events = self.main_component.drag_event(self.size, target, drag.position,
+                                                            DragEvent::Enter(&*drag.payload));
+                self.events.extend(events);
+            }
+
+            drag.target = target;
+        }
+
+        if let Some(ref target) = drag.target {
+            let events = self.main_component.drag_event(self.size, target, drag.position,
+                                                        DragEvent::Over(&*drag.payload));
+            self.events.extend(events);
+        }
+    }
+
+    /// Drops the payload of a drag on its target, or cancels the drag.
+    fn finish_drag(&mut self, drag: Drag, drop: bool) {
+        let Drag { source, payload, target, position, .. } = drag;
+
+        match target {
+            Some(target) if drop => {
+                let events = self.main_component.drag_event(self.size, &target, position,
+                                                            DragEvent::Drop(payload));
+                self.events.extend(events);
+
+                let events = self.main_component.drag_event(self.size, &source, position,
+                                                            DragEvent::Dropped);
+                self.events.extend(events);
+            },
+
+            target => {
+                if let Some(target) = target {
+                    let events = self.main_component.drag_event(self.size, &target, position,
+                                                                DragEvent::Leave);
+                    self.events.extend(events);
+                }
+
+                let events = self.main_component.drag_event(self.size, &source, position,
+                                                            DragEvent::Cancelled);
+                self.events.extend(events);
+            },
+        }
+    }
+
     fn update(&mut self) {
-        let mouse = self.mouse.as_ref().map(|&coords| {
-            Vec2::new(
-                -1.0 + 2.0 * coords.x as f32 / self.viewport.x as f32,
-                1.0 + -2.0 * coords.y as f32 / self.viewport.y as f32,
-            )
-        }).unwrap_or(Vec2::new(-1.0, -1.0));
+        let constraints = self.get_constraints();
+        self.size = self.main_component.get_size(&constraints);
+
+        let viewport = self.viewport;
+        let pixel = self.get_pixel_size();
+        let origin = self.origin;
+        let size = self.size;
+        let old_hovered = self.pointers.iter().filter_map(|p| p.hovered.clone())
+                                              .collect::<Vec<_>>();
+
+        for pointer in self.pointers.iter_mut() {
+            let position = pointer.position.map(|coords| {
+                component_position(viewport, pixel, origin, coords)
+            });
+
+            let events = match (&pointer.capture, position) {
+                (&Some(ref capture), Some(position)) => {
+                    self.main_component.set_captured_pointer_status(size, capture, pointer.id,
+                                                                    position, pointer.pressed)
+                },
+                _ => {
+                    self.main_component.set_pointer_status(size, pointer.id, position,
+                                                           pointer.pressed)
+                },
+            };
 
-        //self.main_component.set_viewport      // TODO: 
-        self.main_component.set_mouse_status(Some(mouse), self.mouse_pressed);
-        self.shapes = self.main_component.render();
+            self.events.extend(events);
+
+            pointer.hovered = match position {
+                Some(position) => self.main_component.hit_path(size, position),
+                None => None,
+            };
+        }
+
+        let new_hovered = self.pointers.iter().filter_map(|p| p.hovered.clone())
+                                              .collect::<Vec<_>>();
+
+        if old_hovered != new_hovered {
+            let old_hovered = old_hovered.iter().map(|p| &p[..]).collect::<Vec<_>>();
+            let new_hovered = new_hovered.iter().map(|p| &p[..]).collect::<Vec<_>>();
+            let events = self.main_component.set_hovered_paths(&old_hovered, &new_hovered);
+            self.events.extend(events);
+        }
+
+        // the events above may have changed the size of the main component
+        let constraints = self.get_constraints();
+        self.size = self.main_component.get_size(&constraints);
+        self.shapes = self.main_component.render(self.size);
+
+        for pointer in self.pointers.iter() {
+            if let Some(ref drag) = pointer.drag {
+                self.shapes.extend(drag.render());
+            }
+        }
+
+        let normalization = self.get_normalization();
+        let height = self.get_constraints().max.y;
+        let shapes = mem::replace(&mut self.shapes, Vec::new());
+        self.shapes = shapes.into_iter().map(|shape| {
+            let shape = match origin {
+                Origin::BottomLeft => shape,
+                Origin::TopLeft => shape.flip_vertically(height),
+            };
+
+            normalize(shape, &normalization)
+        }).collect();
+
+        self.animating = self.main_component.is_animating();
+    }
+}
+
+/// Applies the normalization to a shape.
+///
+/// The normalization doesn't scale both axes by the same factor, which would turn texts into
+/// `Shape::Transformed`. Their position is normalized instead, and their size is expressed
+/// relative to the height of the viewport so that they keep their proportions.
+fn normalize(shape: Shape, normalization: &Transform) -> Shape {
+    match shape {
+        Shape::Text { text, font, bottom_left, em } => {
+            Shape::Text {
+                text: text,
+                font: font,
+                bottom_left: normalization.apply(bottom_left),
+                em: em * normalization.matrix[1][1],
+            }
+        },
+
+        Shape::Clipped { from, to, shape } => {
+            Shape::Clipped {
+                from: normalization.apply(from),
+                to: normalization.apply(to),
+                shape: Box::new(normalize(*shape, normalization)),
+            }
+        },
+
+        shape => shape.transform(normalization),
     }
 }
 
+/// Converts a pixel of the viewport into a position relative to the main component, given the
+/// size of a pixel.
+fn component_position(viewport: Vec2<u32>, pixel: Vec2<f32>, origin: Origin, coords: Vec2<u32>)
+                      -> Vec2<f32>
+{
+    let y = match origin {
+        Origin::BottomLeft => viewport.y as f32 - coords.y as f32,
+        Origin::TopLeft => coords.y as f32,
+    };
+
+    Vec2::new(coords.x as f32 * pixel.x, y * pixel.y)
+}
+
+/// Returns true if two pixels are close enough to be part of the same click.
+fn is_close(settings: &ClickSettings, a: Vec2<u32>, b: Vec2<u32>) -> bool {
+    let dx = a.x as f32 - b.x as f32;
+    let dy = a.y as f32 - b.y as f32;
+    (dx * dx + dy * dy).sqrt() <= settings.click_distance
+}
+
 impl<'a, T, E> Deref for UiMainComponentMutRef<'a, T, E> where T: RawComponent<E> {
     type Target = T;
 
@@ -100,3 +894,394 @@ impl<'a, T: 'a, E> Drop for UiMainComponentMutRef<'a, T, E> where T: RawComponen
         self.ui.update();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vec2;
+
+    use Component;
+    use HoveredStatus;
+    use animation::Tween;
+    use Layout;
+    use PositionnedChild;
+    use component::{MouseButton, PointerEvent, PointerId, ScrollDelta};
+    use predefined::TextComponent;
+    use shape::{Font, Shape};
+    use super::{ClickSettings, Origin, Ui, Unit};
+
+    /// A square of 100 by 100 that describes what happens to it in its events.
+    #[derive(Default)]
+    struct Leaf {
+        handles_scroll: bool,
+        captures: bool,
+        logs_hover: bool,
+    }
+
+    impl Component for Leaf {
+        type EmittedEvent = String;
+        type ReceivedEvent = String;
+
+        fn get_layout(&mut self) -> Layout<String> {
+            Layout::PositionnedChildren(Vec::new(), None)
+        }
+
+        fn get_dimensions(&self) -> Option<Vec2<f32>> {
+            Some(Vec2::new(100.0, 100.0))
+        }
+
+        fn get_bounding_box(&self) -> Option<(Vec2<f32>, Vec2<f32>)> {
+            Some((Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0)))
+        }
+
+        fn handle_pointer_event(&mut self, pointer: PointerId, event: PointerEvent)
+                                -> Option<String>
+        {
+            Some(format!("{:?} {:?}", pointer, event))
+        }
+
+        fn wants_pointer_capture(&mut self, _: PointerId, _: MouseButton) -> bool {
+            self.captures
+        }
+
+        fn handle_captured_pointer_move(&mut self, pointer: PointerId, position: Vec2<f32>,
+                                        pressed: bool) -> Option<String>
+        {
+            Some(format!("{:?} captured at {} {} {}", pointer, position.x, position.y, pressed))
+        }
+
+        fn set_hovered_status(&mut self, status: HoveredStatus) -> Option<String> {
+            if self.logs_hover { Some(format!("{:?}", status)) } else { None }
+        }
+
+        fn handle_scroll(&mut self, delta: ScrollDelta) -> (bool, Option<String>) {
+            if self.handles_scroll {
+                (true, Some(format!("scrolled {:?}", delta)))
+            } else {
+                (false, None)
+            }
+        }
+    }
+
+    /// Two leaves, at `(0, 0)` and `(200, 0)`. Prefixes the events of the leaves with their
+    /// index.
+    #[derive(Default)]
+    struct Row {
+        left: Leaf,
+        right: Leaf,
+        logs_hover: bool,
+    }
+
+    impl Component for Row {
+        type EmittedEvent = String;
+        type ReceivedEvent = String;
+
+        fn get_layout(&mut self) -> Layout<String> {
+            Layout::PositionnedChildren(vec![
+                PositionnedChild::new(&mut self.left, 0.0, 0.0),
+                PositionnedChild::new(&mut self.right, 200.0, 0.0),
+            ], None)
+        }
+
+        fn handle_child_event(&mut self, child_id: usize, event: String) -> Option<String> {
+            Some(format!("{} {}", child_id, event))
+        }
+
+        fn handle_scroll(&mut self, delta: ScrollDelta) -> (bool, Option<String>) {
+            (true, Some(format!("row scrolled {:?}", delta)))
+        }
+
+        fn set_hovered_status(&mut self, status: HoveredStatus) -> Option<String> {
+            if self.logs_hover { Some(format!("row {:?}", status)) } else { None }
+        }
+    }
+
+    /// Builds a `Ui` of 800 by 600 pixels around a `Row`. The pixels `(50, 550)` and
+    /// `(250, 550)` are over the left and the right leaves.
+    fn row_ui() -> Ui<Row, String> {
+        let mut ui: Ui<Row, String> = Ui::new(Default::default(), Vec2::new(800, 600));
+        ui.get_mut_main_component().right.handles_scroll = true;
+        ui
+    }
+
+    /// A leaf whose width grows from 0 to 100 over a second.
+    struct Growing {
+        width: Tween<f32>,
+    }
+
+    impl Component for Growing {
+        type EmittedEvent = ();
+        type ReceivedEvent = ();
+
+        fn get_layout(&mut self) -> Layout<()> {
+            Layout::PositionnedChildren(Vec::new(), None)
+        }
+
+        fn get_dimensions(&self) -> Option<Vec2<f32>> {
+            Some(Vec2::new(self.width.get(), 100.0))
+        }
+
+        fn tick(&mut self, elapsed: f32) -> Option<()> {
+            self.width.advance(elapsed);
+            None
+        }
+
+        fn is_animating(&self) -> bool {
+            self.width.is_running()
+        }
+    }
+
+    fn text_ui() -> Ui<TextComponent, ()> {
+        let text = TextComponent::new("hello".to_string(), Font::Default, 30.0);
+        Ui::new(text, Vec2::new(800, 600))
+    }
+
+    /// Checks that the UI draws a single text, not wrapped in another shape.
+    fn assert_text(ui: &Ui<TextComponent, ()>, expected_bottom_left: Vec2<f32>,
+                   expected_em: f32)
+    {
+        assert_eq!(ui.draw().len(), 1);
+
+        match &ui.draw()[0] {
+            &Shape::Text { ref bottom_left, em, .. } => {
+                assert!((bottom_left.x - expected_bottom_left.x).abs() < 1e-5);
+                assert!((bottom_left.y - expected_bottom_left.y).abs() < 1e-5);
+                assert!((em - expected_em).abs() < 1e-5);
+            },
+            shape => panic!("expected a text, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn texts_stay_texts() {
+        // 30 pixels are a tenth of the normalized height of 2.0
+        assert_text(&text_ui(), Vec2::new(-1.0, -1.0), 0.1);
+    }
+
+    #[test]
+    fn texts_stay_upright_from_the_top() {
+        let mut ui = text_ui();
+        ui.set_origin(Origin::TopLeft);
+        assert_text(&ui, Vec2::new(-1.0, 0.9), 0.1);
+    }
+
+    #[test]
+    fn scroll_goes_to_the_component_under_the_mouse() {
+        let mut ui = row_ui();
+        ui.set_mouse_position(Some(Vec2::new(250, 550)));
+        ui.poll_events();
+
+        ui.scroll(ScrollDelta::Lines(Vec2::new(0.0, 1.0)));
+        assert_eq!(ui.poll_events(), vec!["1 scrolled Lines(Vec2 { x: 0.0, y: 1.0 })"]);
+    }
+
+    #[test]
+    fn unhandled_scroll_bubbles_to_ancestors() {
+        let mut ui = row_ui();
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+        ui.poll_events();
+
+        ui.scroll(ScrollDelta::Lines(Vec2::new(0.0, -2.0)));
+        assert_eq!(ui.poll_events(), vec!["row scrolled Lines(Vec2 { x: 0.0, y: -2.0 })"]);
+    }
+
+    #[test]
+    fn pixel_scrolls_are_converted() {
+        let mut ui = row_ui();
+        ui.set_dpi_factor(2.0);
+        ui.set_mouse_position(Some(Vec2::new(450, 550)));
+        ui.poll_events();
+
+        // 10 physical pixels are 5 logical pixels
+        ui.scroll(ScrollDelta::Pixels(Vec2::new(10.0, 0.0)));
+        assert_eq!(ui.poll_events(), vec!["1 scrolled Logical(Vec2 { x: 5.0, y: 0.0 })"]);
+
+        // nothing happens when the mouse is out of the window
+        ui.set_mouse_position(None);
+        ui.poll_events();
+        ui.scroll(ScrollDelta::Lines(Vec2::new(0.0, 1.0)));
+        assert!(ui.poll_events().is_empty());
+    }
+
+    #[test]
+    fn clicks_and_double_clicks() {
+        let mut ui = row_ui();
+        ui.set_click_settings(ClickSettings { double_click_time: 0.5, .. Default::default() });
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+
+        ui.set_mouse_pressed(true);
+        ui.set_mouse_pressed(false);
+        ui.tick(0.2);
+        ui.set_mouse_pressed(true);
+        ui.set_mouse_pressed(false);
+        assert_eq!(ui.poll_events(), vec!["0 Mouse Pressed(Left)", "0 Mouse Released(Left)",
+                                          "0 Mouse Click(Left)", "0 Mouse Pressed(Left)",
+                                          "0 Mouse Released(Left)", "0 Mouse Click(Left)",
+                                          "0 Mouse DoubleClick(Left)"]);
+
+        // too slow for a double click
+        ui.tick(0.6);
+        ui.set_mouse_button_pressed(MouseButton::Right, true);
+        ui.set_mouse_button_pressed(MouseButton::Right, false);
+        ui.tick(0.6);
+        ui.set_mouse_button_pressed(MouseButton::Right, true);
+        ui.set_mouse_button_pressed(MouseButton::Right, false);
+        assert_eq!(ui.poll_events(), vec!["0 Mouse Pressed(Right)", "0 Mouse Released(Right)",
+                                          "0 Mouse Click(Right)", "0 Mouse Pressed(Right)",
+                                          "0 Mouse Released(Right)", "0 Mouse Click(Right)"]);
+    }
+
+    #[test]
+    fn no_click_when_the_pointer_moved_away() {
+        let mut ui = row_ui();
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+        ui.set_mouse_pressed(true);
+        ui.set_mouse_position(Some(Vec2::new(90, 550)));
+        ui.set_mouse_pressed(false);
+        assert_eq!(ui.poll_events(), vec!["0 Mouse Pressed(Left)", "0 Mouse Released(Left)"]);
+    }
+
+    #[test]
+    fn long_presses_replace_clicks() {
+        let mut ui = row_ui();
+        ui.set_click_settings(ClickSettings { long_press_time: 0.8, .. Default::default() });
+        ui.set_mouse_position(Some(Vec2::new(250, 550)));
+
+        ui.set_mouse_pressed(true);
+        ui.tick(0.5);
+        assert_eq!(ui.poll_events(), vec!["1 Mouse Pressed(Left)"]);
+
+        ui.tick(0.5);
+        ui.tick(0.5);
+        assert_eq!(ui.poll_events(), vec!["1 Mouse LongPress(Left)"]);
+
+        ui.set_mouse_pressed(false);
+        assert_eq!(ui.poll_events(), vec!["1 Mouse Released(Left)"]);
+    }
+
+    #[test]
+    fn touches_are_independent() {
+        let mut ui = row_ui();
+        ui.set_pointer_position(PointerId::Touch(1), Some(Vec2::new(50, 550)));
+        ui.set_pointer_position(PointerId::Touch(2), Some(Vec2::new(250, 550)));
+        assert_eq!(ui.get_hovered(PointerId::Touch(1)), Some(&[0][..]));
+        assert_eq!(ui.get_hovered(PointerId::Touch(2)), Some(&[1][..]));
+        assert_eq!(ui.get_hovered(PointerId::Mouse), None);
+
+        ui.set_pointer_button_pressed(PointerId::Touch(1), MouseButton::Left, true);
+        ui.set_pointer_button_pressed(PointerId::Touch(2), MouseButton::Left, true);
+        ui.set_pointer_button_pressed(PointerId::Touch(1), MouseButton::Left, false);
+        assert_eq!(ui.poll_events(), vec!["0 Touch(1) Pressed(Left)", "1 Touch(2) Pressed(Left)",
+                                          "0 Touch(1) Released(Left)", "0 Touch(1) Click(Left)"]);
+
+        // a removed pointer releases its buttons without clicking
+        ui.remove_pointer(PointerId::Touch(2));
+        assert_eq!(ui.poll_events(), vec!["1 Touch(2) Released(Left)"]);
+        assert_eq!(ui.get_hovered(PointerId::Touch(2)), None);
+        assert_eq!(ui.get_hovered(PointerId::Touch(1)), Some(&[0][..]));
+    }
+
+    #[test]
+    fn captured_pointers_follow_the_component() {
+        let mut ui = row_ui();
+        ui.get_mut_main_component().left.captures = true;
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+        ui.set_mouse_pressed(true);
+        assert_eq!(ui.get_capture(PointerId::Mouse), Some(&[0][..]));
+        assert_eq!(ui.poll_events(), vec!["0 Mouse Pressed(Left)",
+                                          "0 Mouse captured at 50 50 true"]);
+
+        // the leaf keeps receiving the moves outside of it, even over the other leaf
+        ui.set_mouse_position(Some(Vec2::new(250, 300)));
+        assert_eq!(ui.poll_events(), vec!["0 Mouse captured at 250 300 true"]);
+
+        // and the release, before losing the pointer
+        ui.set_mouse_pressed(false);
+        assert_eq!(ui.poll_events(), vec!["0 Mouse Released(Left)",
+                                          "0 Mouse captured at 250 300 false"]);
+        assert_eq!(ui.get_capture(PointerId::Mouse), None);
+
+        ui.set_mouse_position(Some(Vec2::new(260, 550)));
+        assert!(ui.poll_events().is_empty());
+        assert_eq!(ui.get_hovered(PointerId::Mouse), Some(&[1][..]));
+    }
+
+    #[test]
+    fn hovered_status() {
+        let mut ui = row_ui();
+        {
+            let mut row = ui.get_mut_main_component();
+            row.logs_hover = true;
+            row.left.logs_hover = true;
+            row.right.logs_hover = true;
+        }
+        ui.poll_events();
+
+        ui.set_mouse_position(Some(Vec2::new(50, 550)));
+        assert_eq!(ui.poll_events(), vec!["0 Hovered", "row ChildHovered"]);
+        ui.set_mouse_position(Some(Vec2::new(60, 550)));
+        assert!(ui.poll_events().is_empty());
+        ui.set_mouse_position(Some(Vec2::new(250, 550)));
+        assert_eq!(ui.poll_events(), vec!["0 NotHovered", "1 Hovered"]);
+
+        // a component stays hovered while any pointer is over it
+        ui.set_pointer_position(PointerId::Touch(3), Some(Vec2::new(50, 550)));
+        assert_eq!(ui.poll_events(), vec!["0 Hovered"]);
+        ui.set_mouse_position(Some(Vec2::new(500, 300)));
+        assert_eq!(ui.poll_events(), vec!["1 NotHovered"]);
+        ui.remove_pointer(PointerId::Touch(3));
+        assert_eq!(ui.poll_events(), vec!["0 NotHovered", "row NotHovered"]);
+    }
+
+    #[test]
+    fn normalized_coordinates_are_centered() {
+        let mut ui = row_ui();
+        ui.set_unit(Unit::Normalized);
+        assert_eq!(ui.get_constraints().max, Vec2::new(2.0, 2.0));
+        assert_eq!(ui.get_main_component_position(), Vec2::new(-1.0, -1.0));
+
+        assert_eq!(ui.pixels_to_logical(Vec2::new(0, 0)), Vec2::new(-1.0, 1.0));
+        assert_eq!(ui.pixels_to_logical(Vec2::new(400, 300)), Vec2::new(0.0, 0.0));
+        assert_eq!(ui.pixels_to_logical(Vec2::new(800, 600)), Vec2::new(1.0, -1.0));
+
+        assert_eq!(ui.logical_to_pixels(Vec2::new(0.0, 0.0)), Vec2::new(400, 300));
+        assert_eq!(ui.logical_to_pixels(Vec2::new(-0.5, 0.5)), Vec2::new(200, 150));
+        assert_eq!(ui.logical_to_pixels(Vec2::new(-2.0, -2.0)), Vec2::new(0, 599));
+    }
+
+    #[test]
+    fn logical_pixels_follow_the_dpi_factor() {
+        let mut ui = row_ui();
+        ui.set_dpi_factor(2.0);
+        assert_eq!(ui.get_constraints().max, Vec2::new(400.0, 300.0));
+        assert_eq!(ui.get_main_component_position(), Vec2::new(0.0, 0.0));
+
+        assert_eq!(ui.pixels_to_logical(Vec2::new(0, 600)), Vec2::new(0.0, 0.0));
+        assert_eq!(ui.pixels_to_logical(Vec2::new(400, 300)), Vec2::new(200.0, 150.0));
+        assert_eq!(ui.logical_to_pixels(Vec2::new(200.0, 150.0)), Vec2::new(400, 300));
+        assert_eq!(ui.logical_to_pixels(Vec2::new(25.0, 275.0)), Vec2::new(50, 50));
+
+        // the leaves are 100 logical pixels wide, which is 200 physical pixels
+        ui.set_mouse_position(Some(Vec2::new(150, 550)));
+        assert_eq!(ui.get_hovered(PointerId::Mouse), Some(&[0][..]));
+        ui.set_mouse_position(Some(Vec2::new(250, 550)));
+        assert_eq!(ui.get_hovered(PointerId::Mouse), None);
+    }
+
+    #[test]
+    fn animations_stop_once_the_tweens_finish() {
+        let growing = Growing { width: Tween::new(0.0, 100.0, 1.0) };
+        let mut ui: Ui<Growing, ()> = Ui::new(growing, Vec2::new(800, 600));
+        assert!(ui.is_animating());
+
+        ui.tick(0.5);
+        assert!(ui.is_animating());
+        assert_eq!(ui.get_main_component().width.get(), 50.0);
+
+        ui.tick(0.75);
+        assert!(!ui.is_animating());
+        assert_eq!(ui.get_main_component().width.get(), 100.0);
+
+        ui.get_mut_main_component().width.restart();
+        assert!(ui.is_animating());
+    }
+}